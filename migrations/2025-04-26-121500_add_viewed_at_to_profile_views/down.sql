@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER table profile_views
+DROP column viewed_at;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER table profile_views
+ADD column viewed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp;
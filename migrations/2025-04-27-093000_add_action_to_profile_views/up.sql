@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER table profile_views
+ADD column action text not null default 'VIEWED';
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE table profile_reports (
+    id          uuid primary key default uuid_generate_v4(),
+    reporter_id uuid not null,
+    profile_id  uuid not null,
+    reason      text not null,
+    status      text not null default 'OPEN',
+    created_at  TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (reporter_id, profile_id)
+);
+
+CREATE INDEX profile_reports_profile_id_idx ON profile_reports (profile_id);
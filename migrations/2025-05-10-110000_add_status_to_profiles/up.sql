@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER table profiles
+ADD column status text not null default 'ACTIVE';
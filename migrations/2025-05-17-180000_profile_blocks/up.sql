@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE table profile_blocks (
+    blocker_id uuid not null,
+    blocked_id uuid not null,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY(blocker_id, blocked_id)
+);
+
+CREATE INDEX profile_blocks_blocked_id_idx ON profile_blocks (blocked_id);
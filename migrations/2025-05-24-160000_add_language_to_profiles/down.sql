@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER table profiles
+DROP column language;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER table profiles
+ADD column contact_mode text not null default 'USERNAME';
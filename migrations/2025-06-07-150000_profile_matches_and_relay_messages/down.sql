@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP table relay_messages;
+DROP table profile_matches;
@@ -0,0 +1,30 @@
+-- Your SQL goes here
+CREATE table profile_matches (
+    id         uuid primary key default uuid_generate_v4(),
+    profile_a  uuid not null,
+    profile_b  uuid not null,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    UNIQUE (profile_a, profile_b),
+    CHECK (profile_a < profile_b)
+);
+
+CREATE INDEX profile_matches_profile_b_idx ON profile_matches (profile_b);
+
+INSERT INTO profile_matches (profile_a, profile_b)
+SELECT liked.viewer_id, liked.profile_id
+FROM profile_likes liked
+JOIN profile_likes back
+    ON back.viewer_id = liked.profile_id AND back.profile_id = liked.viewer_id
+WHERE liked.viewer_id < liked.profile_id;
+
+CREATE table relay_messages (
+    id         uuid primary key default uuid_generate_v4(),
+    match_id   uuid not null references profile_matches (id) on delete cascade,
+    sender_id  uuid not null,
+    kind       text not null,
+    body       text null,
+    file_id    text null,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp
+);
+
+CREATE INDEX relay_messages_match_id_idx ON relay_messages (match_id, created_at);
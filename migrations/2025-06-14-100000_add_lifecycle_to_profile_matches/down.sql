@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER table profile_matches
+DROP column status,
+DROP column last_active_at,
+DROP column ended_at;
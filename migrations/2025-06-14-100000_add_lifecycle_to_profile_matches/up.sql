@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER table profile_matches
+ADD column status text not null default 'ACTIVE',
+ADD column last_active_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+ADD column ended_at TIMESTAMP WITHOUT TIME ZONE null;
+
+UPDATE profile_matches SET last_active_at = created_at;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP table sent_nudges;
+
+ALTER table profiles
+DROP column do_not_disturb,
+DROP column timezone;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER table profiles
+ADD column do_not_disturb boolean not null default false,
+ADD column timezone text not null default 'Europe/Moscow';
+
+CREATE table sent_nudges (
+    profile_id uuid not null,
+    kind       text not null,
+    subject_id uuid not null,
+    sent_at    TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (profile_id, kind, subject_id)
+);
+
+CREATE INDEX sent_nudges_sent_at_idx ON sent_nudges (profile_id, sent_at);
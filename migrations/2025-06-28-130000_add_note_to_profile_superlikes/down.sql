@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX profile_superlikes_created_at_idx;
+
+ALTER table profile_superlikes
+DROP column message,
+DROP column voice_file_id,
+DROP column created_at;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER table profile_superlikes
+ADD column message text null,
+ADD column voice_file_id text null,
+ADD column created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp;
+
+CREATE INDEX profile_superlikes_created_at_idx ON profile_superlikes (viewer_id, created_at);
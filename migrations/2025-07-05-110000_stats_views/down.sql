@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW stats_onboarding;
+DROP VIEW stats_genders;
+DROP VIEW stats_daily_matches;
+DROP VIEW stats_daily_likes;
+DROP VIEW stats_daily_profiles;
+DROP VIEW stats_daily_active;
+
+ALTER table profile_likes
+DROP column created_at;
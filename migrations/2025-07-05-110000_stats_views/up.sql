@@ -0,0 +1,44 @@
+-- Your SQL goes here
+ALTER table profile_likes
+ADD column created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp;
+
+CREATE VIEW stats_daily_active AS
+SELECT viewed_at::date AS day, count(DISTINCT viewer_id) AS users
+FROM profile_views
+GROUP BY viewed_at::date;
+
+CREATE VIEW stats_daily_profiles AS
+SELECT created_at::date AS day, count(*) AS profiles
+FROM profiles
+GROUP BY created_at::date;
+
+CREATE VIEW stats_daily_likes AS
+SELECT created_at::date AS day, count(*) AS likes
+FROM profile_likes
+GROUP BY created_at::date;
+
+CREATE VIEW stats_daily_matches AS
+SELECT created_at::date AS day, count(*) AS matches
+FROM profile_matches
+GROUP BY created_at::date;
+
+CREATE VIEW stats_genders AS
+SELECT gender, count(*) AS profiles
+FROM profiles
+WHERE status <> 'BANNED' AND gender <> ''
+GROUP BY gender;
+
+-- Where onboarding stopped, named after the bot state asking for the answer
+CREATE VIEW stats_onboarding AS
+SELECT step, count(*) AS profiles
+FROM (
+    SELECT CASE
+        WHEN age = 0 THEN 'InputAge'
+        WHEN gender = '' THEN 'InputGender'
+        WHEN interests = '' THEN 'InputInterests'
+        ELSE 'Done'
+    END AS step
+    FROM profiles
+    WHERE status <> 'BANNED'
+) steps
+GROUP BY step;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE table outbound_jobs (
+    id           bigserial primary key,
+    chat_id      bigint not null,
+    kind         text not null,
+    body         text,
+    file_id      text,
+    reply_markup text,
+    attempts     integer not null default 0,
+    run_after    TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    created_at   TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp,
+    match_id     uuid
+);
+
+CREATE INDEX outbound_jobs_run_after_idx ON outbound_jobs (run_after, id);
+CREATE INDEX outbound_jobs_match_id_idx ON outbound_jobs (match_id);
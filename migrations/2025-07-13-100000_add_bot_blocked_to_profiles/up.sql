@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER table profiles
+ADD column bot_blocked boolean not null default false;
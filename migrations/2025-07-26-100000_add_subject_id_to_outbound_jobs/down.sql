@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX outbound_jobs_chat_id_idx;
+
+ALTER table outbound_jobs
+DROP column subject_id;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER table outbound_jobs
+ADD column subject_id uuid;
+
+CREATE INDEX outbound_jobs_subject_id_idx ON outbound_jobs (subject_id);
+CREATE INDEX outbound_jobs_chat_id_idx ON outbound_jobs (chat_id);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+CREATE INDEX relay_messages_created_at_idx ON relay_messages (created_at);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE table deleted_profiles (
+    user_hash  bytea primary key,
+    profile_id uuid not null,
+    status     text not null,
+    deleted_at TIMESTAMP WITHOUT TIME ZONE NOT NULL DEFAULT current_timestamp
+);
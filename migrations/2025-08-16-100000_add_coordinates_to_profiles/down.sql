@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER table profiles
+DROP column latitude,
+DROP column longitude;
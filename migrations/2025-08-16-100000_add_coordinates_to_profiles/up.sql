@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER table profiles
+ADD column latitude double precision,
+ADD column longitude double precision;
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+CREATE OR REPLACE VIEW stats_daily_active AS
+SELECT viewed_at::date AS day, count(DISTINCT viewer_id) AS users
+FROM profile_views
+GROUP BY viewed_at::date;
+
+DROP TABLE active_days;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- One row per user and day they sent the bot anything
+CREATE TABLE active_days (
+    user_id BIGINT NOT NULL,
+    day DATE NOT NULL DEFAULT current_date,
+    PRIMARY KEY (user_id, day)
+);
+
+CREATE OR REPLACE VIEW stats_daily_active AS
+SELECT day, count(*) AS users
+FROM active_days
+GROUP BY day;
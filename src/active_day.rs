@@ -0,0 +1,45 @@
+use crate::pg::establish_connection;
+use diesel::{ExpressionMethods, RunQueryDsl};
+
+/// Days a user interacted with the bot, the source of the daily active users
+/// in the stats.
+pub struct ActiveDay;
+
+impl ActiveDay {
+    /// Marks the user active today, by the database's date.
+    pub fn record(user_id: i64) -> anyhow::Result<()> {
+        use crate::schema::active_days;
+        let connection = &mut establish_connection();
+        diesel::insert_into(active_days::table)
+            .values(active_days::user_id.eq(user_id))
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg::init_test_pool;
+    use crate::schema::active_days;
+    use crate::stats::Stats;
+    use diesel::QueryDsl;
+
+    #[test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    fn counts_a_user_once_a_day() {
+        init_test_pool();
+        let user_id = -(std::process::id() as i64);
+        let before = Stats::collect().unwrap().active_today;
+        ActiveDay::record(user_id).unwrap();
+        ActiveDay::record(user_id).unwrap();
+        let after = Stats::collect().unwrap().active_today;
+
+        let connection = &mut establish_connection();
+        diesel::delete(active_days::table.filter(active_days::user_id.eq(user_id)))
+            .execute(connection)
+            .unwrap();
+        assert_eq!(after, before + 1);
+    }
+}
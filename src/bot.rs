@@ -1,142 +1,659 @@
-use crate::match_engine::MatchEngine;
-use crate::profile::{Profile, ProfileGender};
+use crate::active_day::ActiveDay;
+use crate::config::Config;
+use crate::data_export::DataExport;
+use crate::i18n::{Lang, Msg};
+use crate::input_failures::{ESCAPE_HATCH_AFTER, InputFailures};
+use crate::match_engine::{MatchEngine, MatchResult};
+use crate::moderation::Moderation;
+use crate::nudge_scheduler::NudgeScheduler;
+use crate::outbound_job::{Delivery, OutboundJob};
+use crate::outbound_queue::OutboundQueue;
+use crate::profile::{ContactMode, Profile, ProfileGender, ProfileInterest, ProfileStatus};
 use crate::profile_activities::ProfileActivity;
-use crate::profile_view::ProfileView;
-use log::{debug, info};
+use crate::profile_block::ProfileBlock;
+use crate::profile_card::{
+    CardAudience, DESCRIPTION_LIMIT, ProfileCard, contact_html, short_label,
+};
+use crate::profile_like::ProfileLike;
+use crate::profile_match::ProfileMatch;
+use crate::profile_report::{ProfileReport, ReportReason, ReportStatus};
+use crate::profile_superlike::{NOTE_LIMIT, ProfileSuperlike};
+use crate::profile_view::{ProfileView, ViewAction};
+use crate::recycle_policy::RecyclePolicy;
+use crate::relay_message::{PRUNE_EVERY, RelayKind, RelayMessage};
+use crate::stats::Stats;
+use crate::swipe_journal::{SwipeJournal, UNDO_WINDOW};
+use chrono_tz::Tz;
+use log::info;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
+use std::time::Duration;
+use teloxide::dispatching::ShutdownToken;
 use teloxide::dispatching::dialogue::{GetChatId, Storage};
-use teloxide::prelude::*;
 use teloxide::sugar::bot::BotMessagesExt;
 use teloxide::types::{
-    InlineQueryResultArticle, InputMessageContent, InputMessageContentText, KeyboardButton,
-    KeyboardMarkup, Me,
-};
-use teloxide::{
-    dispatching::{UpdateHandler, dialogue, dialogue::InMemStorage},
-    prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
-    utils::command::BotCommands,
+    BotCommand, InlineKeyboardButton, InlineKeyboardMarkup, InlineQueryResultArticle, InputFile,
+    InputMessageContent, InputMessageContentText, KeyboardButton, KeyboardMarkup, Me, ParseMode,
+    User,
 };
+use teloxide::utils::html;
+use teloxide::{dispatching::dialogue::InMemStorage, prelude::*, utils::command::BotCommands};
+use tokio::signal::unix::{Signal, SignalKind, signal};
+use tokio::sync::watch;
+use uuid::Uuid;
 
-pub struct SwagaBot {
-    bot: Bot,
-}
+pub struct SwagaBot;
 #[derive(Clone, Default, Debug)]
 pub enum State {
     #[default]
     Start,
-    Profile {
-        username: String,
+    ViewProfiles {
+        profile_id: Uuid,
     },
-    ViewProfiles,
     ListOptions,
     InputAge,
     InputGender,
     InputInterests,
+    InputMedia,
+    InputDescription,
+    Chatting {
+        match_id: Uuid,
+    },
+    InputSuperlike {
+        profile_id: Uuid,
+    },
 }
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
 /// These commands are supported:
-#[derive(BotCommands)]
+#[derive(BotCommands, Clone, Copy)]
 #[command(rename_rule = "lowercase")]
 enum Command {
     /// Display this text
     Help,
     /// Start
     Start,
+    /// Show your profile and the main menu
+    Profile,
+    /// Browse profiles
+    Browse,
+    /// Fill in your profile again
+    Edit,
+    /// List your matches
+    Matches,
+    /// See who liked you
+    Likes,
+    /// Language, visibility and other settings
+    Settings,
+    /// Hide or show your profile
+    Pause,
+    /// List and unblock blocked people
+    Blocked,
+    /// Delete your profile and all data
+    Delete,
+    /// Download all your data
+    Export,
+    /// Change the bot language
+    Language,
+    /// Abort the current step and go back to the menu
+    Cancel,
+}
+
+/// Timezones offered in the settings, used for quiet hours.
+const TIMEZONES: [Tz; 10] = [
+    Tz::Europe__Kaliningrad,
+    Tz::Europe__Moscow,
+    Tz::Europe__Samara,
+    Tz::Asia__Yekaterinburg,
+    Tz::Asia__Omsk,
+    Tz::Asia__Novosibirsk,
+    Tz::Asia__Irkutsk,
+    Tz::Asia__Vladivostok,
+    Tz::Europe__Berlin,
+    Tz::Europe__London,
+];
+
+/// Commands shown in the Telegram menu, with their catalog descriptions.
+const MENU_COMMANDS: [(&str, Msg); 13] = [
+    ("start", Msg::CmdStart),
+    ("profile", Msg::CmdProfile),
+    ("browse", Msg::CmdBrowse),
+    ("edit", Msg::CmdEdit),
+    ("matches", Msg::CmdMatches),
+    ("likes", Msg::CmdLikes),
+    ("settings", Msg::CmdSettings),
+    ("pause", Msg::CmdPause),
+    ("blocked", Msg::CmdBlocked),
+    ("language", Msg::CmdLanguage),
+    ("export", Msg::CmdExport),
+    ("delete", Msg::CmdDelete),
+    ("cancel", Msg::CmdCancel),
+];
+
+/// Commands available to the admins listed in `ADMIN_IDS`:
+#[derive(BotCommands)]
+#[command(rename_rule = "lowercase")]
+enum AdminCommand {
+    /// Review reported profiles
+    ModQueue,
+    /// Show usage statistics
+    Stats,
+    /// Inspect a profile by Telegram id, @username or profile id
+    User(String),
+    /// Send a message to every user
+    Broadcast(String),
 }
 
+/// Ages accepted during onboarding.
+const AGE_RANGE: RangeInclusive<i32> = 14..=100;
+
+/// How many reported profiles `/modqueue` shows at once.
+const MOD_QUEUE_PAGE: i64 = 10;
+
 type MyDialogue = Arc<InMemStorage<State>>;
 
 impl SwagaBot {
     /// Parse the text wrote on Telegram and check if that text is a valid command
     /// or not, then match the command. If the command is `/start` it writes a
     /// markup with the `InlineKeyboardMarkup`.
+    #[allow(clippy::too_many_arguments)]
     async fn message_handler(
         bot: Bot,
         dialogue: MyDialogue,
         msg: Message,
         me: Me,
+        engine: Arc<MatchEngine>,
+        moderation: Arc<Moderation>,
+        failures: Arc<InputFailures>,
+        journal: Arc<SwipeJournal>,
+        config: Arc<Config>,
     ) -> HandlerResult {
         let chat_id = msg.chat_id().unwrap();
-        let username = msg.from.clone().unwrap().username.unwrap();
+        let from = msg.from.clone().unwrap();
+        let lang = Self::lang_of(&from, config.default_language)?;
+        let username = from.username.unwrap();
+        if Profile::get_status_by_user_id(from.id.0 as i64)? == Some(ProfileStatus::Banned) {
+            bot.send_message(chat_id, lang.t(Msg::AccountBanned))
+                .await?;
+            return Ok(());
+        }
+        ActiveDay::record(from.id.0 as i64)?;
+        if !msg.text().is_some_and(|text| text.starts_with("/"))
+            && let Some(State::Chatting { match_id }) =
+                dialogue.clone().get_dialogue(chat_id).await?
+        {
+            return SwagaBot::relay_message(&bot, dialogue, &msg, match_id, &username, lang).await;
+        }
+        if !msg.text().is_some_and(|text| text.starts_with("/"))
+            && let Some(state @ State::InputSuperlike { profile_id }) =
+                dialogue.clone().get_dialogue(chat_id).await?
+        {
+            return SwagaBot::send_superlike(
+                &bot,
+                dialogue,
+                &msg,
+                &state,
+                profile_id,
+                &username,
+                &engine,
+                config.daily_superlikes(),
+                lang,
+                &failures,
+            )
+            .await;
+        }
+        if let Some(location) = msg.location() {
+            Profile::update_coordinates(&username, location.latitude, location.longitude)?;
+            bot.send_message(chat_id, lang.t(Msg::LocationSaved))
+                .await?;
+            return Ok(());
+        }
+        if !msg.text().is_some_and(|text| text.starts_with("/"))
+            && let Some(state @ State::InputMedia) = dialogue.clone().get_dialogue(chat_id).await?
+        {
+            return SwagaBot::save_photo(&bot, dialogue, &msg, &state, &username, lang, &failures)
+                .await;
+        }
         if let Some(text) = msg.text() {
+            if text.starts_with("/")
+                && moderation.is_admin(from.id.0 as i64)
+                && let Ok(command) = AdminCommand::parse(text, me.username())
+            {
+                return match command {
+                    AdminCommand::ModQueue => SwagaBot::show_mod_queue(&bot, chat_id, lang).await,
+                    AdminCommand::Stats => SwagaBot::show_stats(&bot, chat_id, lang).await,
+                    AdminCommand::User(query) => {
+                        SwagaBot::inspect_user(&bot, chat_id, &query, lang).await
+                    }
+                    AdminCommand::Broadcast(text) => {
+                        SwagaBot::broadcast(&bot, chat_id, text, lang).await
+                    }
+                };
+            }
+            if text.starts_with("/") {
+                let Ok(command) = Command::parse(text, me.username()) else {
+                    bot.send_message(msg.chat.id, lang.t(Msg::CommandNotFound))
+                        .await?;
+                    return Ok(());
+                };
+                failures.clear(chat_id);
+                return SwagaBot::handle_command(
+                    &bot, dialogue, &msg, command, &username, &engine, lang,
+                )
+                .await;
+            }
             if let Some(state) = dialogue.clone().get_dialogue(chat_id).await? {
                 info!("{:?}", state);
-                match state {
-                    State::ViewProfiles => {
-                        SwagaBot::next_profile(&bot, dialogue, chat_id, &username).await?;
+                match state.clone() {
+                    State::ViewProfiles { profile_id } => {
+                        let buttons = [
+                            Msg::BtnLike,
+                            Msg::BtnSuperlike,
+                            Msg::BtnSkip,
+                            Msg::BtnUndo,
+                            Msg::BtnReport,
+                            Msg::BtnBlock,
+                            Msg::BtnMenu,
+                        ];
+                        let action = match Msg::from_button(text, &buttons) {
+                            Some(Msg::BtnLike) => ViewAction::Liked,
+                            Some(Msg::BtnSkip) => ViewAction::Skipped,
+                            Some(Msg::BtnUndo) => {
+                                failures.clear(chat_id);
+                                return SwagaBot::undo_swipe(
+                                    &bot, dialogue, chat_id, profile_id, &username, &journal, lang,
+                                )
+                                .await;
+                            }
+                            Some(Msg::BtnSuperlike) => {
+                                failures.clear(chat_id);
+                                return SwagaBot::ask_superlike_note(
+                                    &bot,
+                                    dialogue,
+                                    chat_id,
+                                    profile_id,
+                                    &username,
+                                    config.daily_superlikes(),
+                                    lang,
+                                )
+                                .await;
+                            }
+                            Some(Msg::BtnReport) => {
+                                SwagaBot::ask_report_reason(&bot, chat_id, profile_id, lang)
+                                    .await?;
+                                failures.clear(chat_id);
+                                return Ok(());
+                            }
+                            Some(Msg::BtnBlock) => {
+                                SwagaBot::block_profile(&username, profile_id)?;
+                                failures.clear(chat_id);
+                                SwagaBot::next_profile(
+                                    &bot, dialogue, chat_id, &username, &engine, lang,
+                                )
+                                .await?;
+                                return Ok(());
+                            }
+                            Some(_) => {
+                                failures.clear(chat_id);
+                                SwagaBot::open_menu(&bot, dialogue, chat_id, &username, lang)
+                                    .await?;
+                                return Ok(());
+                            }
+                            None => {
+                                return SwagaBot::reject_input(
+                                    &bot, chat_id, &state, &username, lang, &failures,
+                                )
+                                .await;
+                            }
+                        };
+                        SwagaBot::rate_profile(&username, profile_id, action, true)?;
+                        journal.record(chat_id, profile_id, action);
+                        SwagaBot::next_profile(&bot, dialogue, chat_id, &username, &engine, lang)
+                            .await?;
                     }
-                    State::ListOptions => match text.parse::<i32>() {
-                        Ok(input_option) => match input_option {
-                            1 => SwagaBot::next_profile(&bot, dialogue, chat_id, &username).await?,
-                            2 => SwagaBot::refresh_profile(&bot, dialogue, chat_id).await?,
-                            _ => SwagaBot::handle_generic_error(&bot, dialogue, chat_id).await?,
-                        },
-                        Err(_) => {
-                            SwagaBot::handle_generic_error(&bot, dialogue, chat_id).await?;
+                    State::ListOptions => match text.trim().parse::<i32>() {
+                        Ok(1) => {
+                            SwagaBot::next_profile(
+                                &bot, dialogue, chat_id, &username, &engine, lang,
+                            )
+                            .await?
+                        }
+                        Ok(2) => SwagaBot::refresh_profile(&bot, dialogue, chat_id, lang).await?,
+                        Ok(3) => {
+                            bot.send_message(chat_id, lang.t(Msg::AskMedia)).await?;
+                            dialogue.update_dialogue(chat_id, State::InputMedia).await?;
+                        }
+                        Ok(4) => {
+                            bot.send_message(
+                                chat_id,
+                                lang.f(Msg::AskDescription, &[("limit", &DESCRIPTION_LIMIT)]),
+                            )
+                            .await?;
+                            dialogue
+                                .update_dialogue(chat_id, State::InputDescription)
+                                .await?;
+                        }
+                        Ok(5) => SwagaBot::toggle_pause(&bot, chat_id, &username, lang).await?,
+                        _ => {
+                            return SwagaBot::reject_input(
+                                &bot, chat_id, &state, &username, lang, &failures,
+                            )
+                            .await;
                         }
                     },
-                    State::InputAge => match text.parse::<i32>() {
-                        Ok(age) => {
-                            SwagaBot::save_age(&bot, dialogue, chat_id, age, &username).await?;
+                    State::InputAge => match text.trim().parse::<i32>() {
+                        Ok(age) if AGE_RANGE.contains(&age) => {
+                            SwagaBot::save_age(&bot, dialogue, chat_id, age, &username, lang)
+                                .await?;
                         }
-                        Err(_) => {
-                            SwagaBot::handle_generic_error(&bot, dialogue, chat_id).await?;
+                        _ => {
+                            return SwagaBot::reject_input(
+                                &bot, chat_id, &state, &username, lang, &failures,
+                            )
+                            .await;
                         }
                     },
                     State::InputInterests => {
-                        let interest = match text {
-                            "Девушки" => {}
-                            "Парни" => {}
-                            "Все равно" => {}
-                            _ => {
-                                SwagaBot::handle_generic_error(&bot, dialogue, chat_id).await?;
-                                return Ok(());
+                        let buttons = [
+                            Msg::BtnInterestFemale,
+                            Msg::BtnInterestMale,
+                            Msg::BtnInterestAny,
+                        ];
+                        let interest = match Msg::from_button(text, &buttons) {
+                            Some(Msg::BtnInterestFemale) => ProfileInterest::Female,
+                            Some(Msg::BtnInterestMale) => ProfileInterest::Male,
+                            Some(_) => ProfileInterest::Any,
+                            None => {
+                                return SwagaBot::reject_input(
+                                    &bot, chat_id, &state, &username, lang, &failures,
+                                )
+                                .await;
                             }
                         };
+                        SwagaBot::save_interests(
+                            &bot, dialogue, chat_id, interest, &username, lang,
+                        )
+                        .await?;
                     }
                     State::InputGender => {
-                        let gender: ProfileGender = match text {
-                            "Я девушка" => ProfileGender::Female,
-                            "Я парень" => ProfileGender::Male,
-                            _ => {
-                                Self::handle_generic_error(&bot, dialogue, chat_id).await?;
-                                return Ok(());
+                        let buttons = [Msg::BtnGenderFemale, Msg::BtnGenderMale];
+                        let gender: ProfileGender = match Msg::from_button(text, &buttons) {
+                            Some(Msg::BtnGenderFemale) => ProfileGender::Female,
+                            Some(_) => ProfileGender::Male,
+                            None => {
+                                return SwagaBot::reject_input(
+                                    &bot, chat_id, &state, &username, lang, &failures,
+                                )
+                                .await;
                             }
                         };
-                        SwagaBot::save_gender(&bot, dialogue, chat_id, gender, &username).await?;
+                        SwagaBot::save_gender(&bot, dialogue, chat_id, gender, &username, lang)
+                            .await?;
                     }
-                    _ => {}
-                }
-            } else {
-                let is_command = text.starts_with("/");
-                if is_command {
-                    match BotCommands::parse(text, me.username()) {
-                        Ok(Command::Help) => {
-                            SwagaBot::start(bot, &dialogue, msg).await?;
-                        }
-                        Ok(Command::Start) => {
-                            SwagaBot::start(bot, &dialogue, msg).await?;
-                        }
-                        Err(_) => {
-                            bot.send_message(msg.chat.id, "Command not found!").await?;
+                    State::InputDescription => {
+                        let description = text.trim();
+                        if description.is_empty() || description.chars().count() > DESCRIPTION_LIMIT
+                        {
+                            return SwagaBot::reject_input(
+                                &bot, chat_id, &state, &username, lang, &failures,
+                            )
+                            .await;
                         }
+                        Profile::update_description(&username, description)?;
+                        bot.send_message(chat_id, lang.t(Msg::DescriptionSaved))
+                            .await?;
+                        SwagaBot::open_menu(&bot, dialogue, chat_id, &username, lang).await?;
                     }
+                    State::Start
+                    | State::Chatting { .. }
+                    | State::InputSuperlike { .. }
+                    | State::InputMedia => {}
                 }
+                failures.clear(chat_id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a command. Commands work in every state and leave the current step.
+    async fn handle_command(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        msg: &Message,
+        command: Command,
+        username: &str,
+        engine: &MatchEngine,
+        lang: Lang,
+    ) -> HandlerResult {
+        let chat_id = msg.chat.id;
+        match command {
+            Command::Start => return Self::start(bot.clone(), &dialogue, msg.clone(), lang).await,
+            Command::Help => return Self::send_help(bot, chat_id, lang).await,
+            Command::Language => return Self::ask_language(bot, chat_id, username, lang).await,
+            _ => {}
+        }
+        if Profile::get_by_username(username)?.is_none() {
+            bot.send_message(chat_id, lang.t(Msg::NeedStart)).await?;
+            return Ok(());
+        }
+        match command {
+            Command::Profile | Command::Cancel => {
+                Self::open_menu(bot, dialogue, chat_id, username, lang).await
+            }
+            Command::Browse => {
+                Self::next_profile(bot, dialogue, chat_id, username, engine, lang).await
+            }
+            Command::Edit => Self::refresh_profile(bot, dialogue, chat_id, lang).await,
+            Command::Matches => Self::show_matches(bot, chat_id, username, lang).await,
+            Command::Likes => Self::show_likes(bot, chat_id, username, engine, lang).await,
+            Command::Settings => Self::show_settings(bot, chat_id, username, lang).await,
+            Command::Pause => Self::toggle_pause(bot, chat_id, username, lang).await,
+            Command::Blocked => Self::show_blocked(bot, chat_id, username, lang).await,
+            Command::Delete => Self::confirm_delete(bot, chat_id, username, lang).await,
+            Command::Export => Self::export_data(bot, chat_id, username, lang).await,
+            Command::Start | Command::Help | Command::Language => Ok(()),
+        }
+    }
+
+    async fn send_help(bot: &Bot, chat_id: ChatId, lang: Lang) -> HandlerResult {
+        let commands = MENU_COMMANDS
+            .iter()
+            .map(|(command, description)| format!("/{command} - {}", lang.t(*description)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        bot.send_message(chat_id, commands).await?;
+        Ok(())
+    }
+
+    /// Registers the command menu in every supported language. The Russian one
+    /// doubles as the default for clients in other languages.
+    async fn register_commands(bot: &Bot) -> anyhow::Result<()> {
+        for lang in Lang::ALL {
+            let commands: Vec<BotCommand> = MENU_COMMANDS
+                .iter()
+                .map(|(command, description)| BotCommand::new(*command, lang.t(*description)))
+                .collect();
+            bot.set_my_commands(commands.clone())
+                .language_code(lang.to_string())
+                .await?;
+            if lang == Lang::default() {
+                bot.set_my_commands(commands).await?;
             }
         }
+        Ok(())
+    }
 
+    async fn show_matches(bot: &Bot, chat_id: ChatId, username: &str, lang: Lang) -> HandlerResult {
+        let profile = Profile::get_by_username(username)?.unwrap();
+        let mut matches = Vec::new();
+        let mut buttons = Vec::new();
+        for profile_match in ProfileMatch::for_profile(profile.id())? {
+            let matched_id = profile_match.other(profile.id());
+            if ProfileBlock::exists_between(profile.id(), &matched_id)? {
+                continue;
+            }
+            if let Some(matched) = Profile::get_by_id(&matched_id)? {
+                let contact = contact_html(&matched)
+                    .unwrap_or_else(|| html::escape(lang.t(Msg::ContactRelayHint)));
+                let label = short_label(&matched);
+                matches.push(format!("{} - {contact}", html::escape(&label)));
+                buttons.push(vec![
+                    InlineKeyboardButton::callback(
+                        lang.f(Msg::BtnChatWith, &[("name", &label)]),
+                        format!("chat:{}:open", profile_match.id()),
+                    ),
+                    InlineKeyboardButton::callback(
+                        lang.t(Msg::BtnUnmatch),
+                        format!("unmatch:{}:ask", profile_match.id()),
+                    ),
+                ]);
+            }
+        }
+        if matches.is_empty() {
+            bot.send_message(chat_id, lang.t(Msg::MatchesEmpty)).await?;
+            return Ok(());
+        }
+        let text = format!(
+            "{}\n{}",
+            html::escape(lang.t(Msg::MatchesHeader)),
+            matches.join("\n")
+        );
+        bot.send_message(chat_id, text)
+            .parse_mode(ParseMode::Html)
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
+            .await?;
         Ok(())
     }
 
+    /// Shows the first person who liked the user and is still waiting for an
+    /// answer, with buttons that settle it right away.
+    async fn show_likes(
+        bot: &Bot,
+        chat_id: ChatId,
+        username: &str,
+        engine: &MatchEngine,
+        lang: Lang,
+    ) -> HandlerResult {
+        let viewer = Profile::get_by_username(username)?.unwrap();
+        let liked_you = engine.liked_you(viewer.id())?;
+        let Some(liker) = liked_you.first() else {
+            bot.send_message(chat_id, lang.t(Msg::LikesEmpty)).await?;
+            return Ok(());
+        };
+        let buttons = vec![vec![
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnLike),
+                format!("inbox:{}:like", liker.id()),
+            ),
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnSkip),
+                format!("inbox:{}:skip", liker.id()),
+            ),
+        ]];
+        ProfileCard::new(liker, CardAudience::Stranger)
+            .with_distance_from(&viewer)
+            .with_header(lang.f(Msg::LikesHeader, &[("count", &liked_you.len())]))
+            .send(
+                bot,
+                chat_id,
+                Some(InlineKeyboardMarkup::new(buttons).into()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn show_settings(
+        bot: &Bot,
+        chat_id: ChatId,
+        username: &str,
+        lang: Lang,
+    ) -> HandlerResult {
+        let profile = Profile::get_by_username(username)?.unwrap();
+        let visibility = match profile.status() {
+            ProfileStatus::Paused => lang.t(Msg::SettingsHidden),
+            _ => lang.t(Msg::SettingsVisible),
+        };
+        let nudges = match profile.do_not_disturb() {
+            true => lang.t(Msg::SettingsNudgesOff),
+            false => lang.t(Msg::SettingsNudgesOn),
+        };
+        let text = lang.f(
+            Msg::Settings,
+            &[
+                ("language", &lang.native_name()),
+                ("visibility", &visibility),
+                (
+                    "contact",
+                    &Self::contact_mode_name(profile.contact_mode(), lang),
+                ),
+                ("timezone", &profile.timezone().name()),
+                ("nudges", &nudges),
+            ],
+        );
+        let buttons = vec![
+            Lang::ALL
+                .map(|choice| {
+                    InlineKeyboardButton::callback(
+                        choice.native_name(),
+                        format!("lang:{}:{choice}", profile.id()),
+                    )
+                })
+                .to_vec(),
+            ContactMode::ALL
+                .map(|mode| {
+                    InlineKeyboardButton::callback(
+                        Self::contact_mode_name(mode, lang),
+                        format!("privacy:{}:{mode}", profile.id()),
+                    )
+                })
+                .to_vec(),
+            vec![InlineKeyboardButton::callback(
+                lang.t(Msg::BtnTogglePause),
+                format!("pause:{}:toggle", profile.id()),
+            )],
+            vec![
+                InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnToggleNudges),
+                    format!("dnd:{}:toggle", profile.id()),
+                ),
+                InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnTimezone),
+                    format!("tz:{}:ask", profile.id()),
+                ),
+            ],
+        ];
+        bot.send_message(chat_id, text)
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
+            .await?;
+        Ok(())
+    }
+
+    fn contact_mode_name(mode: ContactMode, lang: Lang) -> &'static str {
+        match mode {
+            ContactMode::Username => lang.t(Msg::ContactUsername),
+            ContactMode::DeepLink => lang.t(Msg::ContactDeepLink),
+            ContactMode::Relay => lang.t(Msg::ContactRelay),
+        }
+    }
+
+    /// Language of the sender: the one stored in their profile, otherwise the one
+    /// of their Telegram client, otherwise the configured default.
+    fn lang_of(user: &User, default: Lang) -> anyhow::Result<Lang> {
+        let stored = match user.username {
+            Some(ref username) => Profile::get_by_username(username)?.and_then(|p| p.language()),
+            None => None,
+        };
+        Ok(stored.unwrap_or_else(|| Lang::from_code(user.language_code.as_deref(), default)))
+    }
+
     pub async fn refresh_profile(
         bot: &Bot,
         my_dialogue: MyDialogue,
         chat_id: ChatId,
+        lang: Lang,
     ) -> HandlerResult {
-        bot.send_message(chat_id, "Сколько тебе лет?".to_string())
-            .await?;
+        bot.send_message(chat_id, lang.t(Msg::AskAge)).await?;
         my_dialogue
             .update_dialogue(chat_id, State::InputAge)
             .await?;
@@ -148,43 +665,371 @@ impl SwagaBot {
         my_dialogue: MyDialogue,
         chat_id: ChatId,
         username: &str,
+        engine: &MatchEngine,
+        lang: Lang,
     ) -> HandlerResult {
         let viewer = Profile::get_by_username(username)?.unwrap();
-        let profile_activity = ProfileActivity::from_id(*viewer.id()).upsert_and_increment()?;
-        let matching_engine = MatchEngine::match_profiles(viewer.id())?;
-        let profile = Profile::get_profile();
-        let profile_text = format!(
-            "{}, {}, {} - {}",
-            profile.username(),
-            profile.age(),
-            profile.location(),
-            profile.description()
-        );
-        bot.send_message(chat_id, profile_text).await?;
+        let activity = ProfileActivity::from_id(*viewer.id()).upsert_and_increment()?;
+        let profile = match engine.match_profiles(viewer.id(), *activity.activity_count())? {
+            MatchResult::Fresh(profile) => profile,
+            MatchResult::Recycled(profile) => {
+                bot.send_message(chat_id, lang.t(Msg::RecycledProfiles))
+                    .await?;
+                profile
+            }
+            MatchResult::Exhausted => {
+                bot.send_message(chat_id, lang.t(Msg::ProfilesExhausted))
+                    .await?;
+                return Self::open_menu(bot, my_dialogue, chat_id, username, lang).await;
+            }
+        };
+        Self::show_candidate(bot, my_dialogue, chat_id, &viewer, &profile, lang).await
+    }
+
+    async fn show_candidate(
+        bot: &Bot,
+        my_dialogue: MyDialogue,
+        chat_id: ChatId,
+        viewer: &Profile,
+        profile: &Profile,
+        lang: Lang,
+    ) -> HandlerResult {
+        ProfileCard::new(profile, CardAudience::Stranger)
+            .with_distance_from(viewer)
+            .send(bot, chat_id, Some(Self::swipe_keyboard(lang).into()))
+            .await?;
         let view = ProfileView::new(*viewer.id(), *profile.id());
         view.insert()?;
+        my_dialogue
+            .update_dialogue(
+                chat_id,
+                State::ViewProfiles {
+                    profile_id: *profile.id(),
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Takes back the last like or skip and shows that profile again. The card
+    /// shown after it goes back to the unseen pile.
+    async fn undo_swipe(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        chat_id: ChatId,
+        current_id: Uuid,
+        username: &str,
+        journal: &SwipeJournal,
+        lang: Lang,
+    ) -> HandlerResult {
+        let viewer = Profile::get_by_username(username)?.unwrap();
+        let Some(swipe) = journal.take(chat_id) else {
+            bot.send_message(chat_id, lang.t(Msg::UndoUnavailable))
+                .await?;
+            return Ok(());
+        };
+        if swipe.action == ViewAction::Liked {
+            ProfileLike::new(*viewer.id(), swipe.profile_id).delete()?;
+            if let Some(match_id) =
+                ProfileMatch::delete_created_within(viewer.id(), &swipe.profile_id, UNDO_WINDOW)?
+            {
+                OutboundJob::delete_for_match(&match_id)?;
+            }
+        }
+        ProfileView::new(*viewer.id(), swipe.profile_id).set_action(ViewAction::Viewed)?;
+        if current_id != swipe.profile_id {
+            ProfileView::new(*viewer.id(), current_id).delete()?;
+        }
+        let Some(profile) = Profile::get_by_id(&swipe.profile_id)? else {
+            bot.send_message(chat_id, lang.t(Msg::UndoUnavailable))
+                .await?;
+            return Ok(());
+        };
+        bot.send_message(chat_id, lang.t(Msg::UndoDone)).await?;
+        Self::show_candidate(bot, dialogue, chat_id, &viewer, &profile, lang).await
+    }
+
+    /// Stores the viewer's reaction to the shown profile and creates the match
+    /// when the like is mutual. For an `undoable` swipe the match announcements
+    /// wait out the undo window, so an undone like is never announced.
+    pub fn rate_profile(
+        username: &str,
+        profile_id: Uuid,
+        action: ViewAction,
+        undoable: bool,
+    ) -> anyhow::Result<()> {
+        let viewer = Profile::get_by_username(username)?.unwrap();
+        ProfileView::new(*viewer.id(), profile_id).set_action(action)?;
+        if action != ViewAction::Liked {
+            return Ok(());
+        }
+        let like = ProfileLike::new(*viewer.id(), profile_id);
+        like.insert()?;
+        if like.is_mutual()? {
+            let delay = undoable.then_some(UNDO_WINDOW);
+            Self::confirm_match(*viewer.id(), profile_id, delay)?;
+        }
         Ok(())
     }
 
+    /// Creates the match and queues the announcements for both sides.
+    fn confirm_match(
+        viewer_id: Uuid,
+        profile_id: Uuid,
+        delay: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        if ProfileBlock::exists_between(&viewer_id, &profile_id)? {
+            return Ok(());
+        }
+        let (Some(viewer), Some(liked)) = (
+            Profile::get_by_id(&viewer_id)?,
+            Profile::get_by_id(&profile_id)?,
+        ) else {
+            return Ok(());
+        };
+        let profile_match = ProfileMatch::create(viewer_id, profile_id)?;
+        // An ended match stays ended
+        if profile_match.is_active() {
+            if liked.may_reach_others() {
+                Self::notify_match(&viewer, &liked, profile_match.id(), delay)?;
+            }
+            if viewer.may_reach_others() {
+                Self::notify_match(&liked, &viewer, profile_match.id(), delay)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn ask_superlike_note(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        chat_id: ChatId,
+        profile_id: Uuid,
+        username: &str,
+        allowance: i64,
+        lang: Lang,
+    ) -> HandlerResult {
+        let viewer = Profile::get_by_username(username)?.unwrap();
+        if ProfileSuperlike::remaining_today(viewer.id(), allowance)? == 0 {
+            bot.send_message(chat_id, lang.t(Msg::SuperlikeLimit))
+                .reply_markup(Self::swipe_keyboard(lang))
+                .await?;
+            return Ok(());
+        }
+        dialogue
+            .update_dialogue(chat_id, State::InputSuperlike { profile_id })
+            .await?;
+        bot.send_message(
+            chat_id,
+            lang.f(Msg::AskSuperlikeNote, &[("limit", &NOTE_LIMIT)]),
+        )
+        .reply_markup(Self::superlike_keyboard(lang))
+        .await?;
+        Ok(())
+    }
+
+    /// Takes the note for a superlike, delivers it and moves on to the next
+    /// card. The superlike also counts as a regular like.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_superlike(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        msg: &Message,
+        state: &State,
+        profile_id: Uuid,
+        username: &str,
+        engine: &MatchEngine,
+        allowance: i64,
+        lang: Lang,
+        failures: &InputFailures,
+    ) -> HandlerResult {
+        let chat_id = msg.chat.id;
+        let (note, voice) = if let Some(text) = msg.text() {
+            match Msg::from_button(text, &[Msg::BtnNoNote, Msg::BtnCancel]) {
+                Some(Msg::BtnCancel) => {
+                    failures.clear(chat_id);
+                    dialogue
+                        .update_dialogue(chat_id, State::ViewProfiles { profile_id })
+                        .await?;
+                    bot.send_message(chat_id, lang.t(Msg::SuperlikeCancelled))
+                        .reply_markup(Self::swipe_keyboard(lang))
+                        .await?;
+                    return Ok(());
+                }
+                Some(_) => (None, None),
+                None if text.trim().chars().count() <= NOTE_LIMIT => {
+                    (Some(text.trim().to_string()), None)
+                }
+                None => {
+                    return Self::reject_input(bot, chat_id, state, username, lang, failures).await;
+                }
+            }
+        } else if let Some(voice) = msg.voice() {
+            (None, Some(voice.file.id.to_string()))
+        } else {
+            return Self::reject_input(bot, chat_id, state, username, lang, failures).await;
+        };
+        failures.clear(chat_id);
+        let sender = Profile::get_by_username(username)?.unwrap();
+        // Checked again in case the allowance was spent in the meantime
+        if ProfileSuperlike::remaining_today(sender.id(), allowance)? == 0 {
+            dialogue
+                .update_dialogue(chat_id, State::ViewProfiles { profile_id })
+                .await?;
+            bot.send_message(chat_id, lang.t(Msg::SuperlikeLimit))
+                .reply_markup(Self::swipe_keyboard(lang))
+                .await?;
+            return Ok(());
+        }
+        let superlike = ProfileSuperlike::new(*sender.id(), profile_id, note, voice);
+        if !superlike.insert()? {
+            bot.send_message(chat_id, lang.t(Msg::SuperlikeAlreadySent))
+                .await?;
+        } else {
+            if sender.may_reach_others()
+                && !ProfileBlock::exists_between(sender.id(), &profile_id)?
+                && let Some(recipient) = Profile::get_by_id(&profile_id)?
+            {
+                Self::notify_superlike(&sender, &recipient, &superlike)?;
+            }
+            bot.send_message(chat_id, lang.t(Msg::SuperlikeSent))
+                .await?;
+        }
+        Self::rate_profile(username, profile_id, ViewAction::Liked, false)?;
+        Self::next_profile(bot, dialogue, chat_id, username, engine, lang).await
+    }
+
+    /// Queues the superlike for its recipient, with buttons to like back or
+    /// skip.
+    fn notify_superlike(
+        sender: &Profile,
+        recipient: &Profile,
+        superlike: &ProfileSuperlike,
+    ) -> anyhow::Result<()> {
+        let lang = recipient.language().unwrap_or_default();
+        let buttons = InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnLike),
+                format!("inbox:{}:like", sender.id()),
+            ),
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnSkip),
+                format!("inbox:{}:skip", sender.id()),
+            ),
+        ]]);
+        let mut header = lang.t(Msg::SuperlikeReceived).to_string();
+        if let Some(note) = superlike.message() {
+            header = format!("{header}\n«{note}»");
+        }
+        let card = ProfileCard::new(sender, CardAudience::Stranger)
+            .with_distance_from(recipient)
+            .with_header(header);
+        let recipient_chat = ChatId(*recipient.user_id());
+        match superlike.voice_file_id() {
+            Some(file_id) => {
+                card.enqueue(recipient_chat, None, Delivery::default())?;
+                OutboundQueue::voice(
+                    recipient_chat,
+                    file_id,
+                    None,
+                    Some(buttons.into()),
+                    Delivery {
+                        subject_id: Some(*sender.id()),
+                        ..Delivery::default()
+                    },
+                )?;
+            }
+            None => card.enqueue(recipient_chat, Some(buttons.into()), Delivery::default())?,
+        }
+        Ok(())
+    }
+
+    /// Queues the match announcement, held back for `delay` if set.
+    fn notify_match(
+        recipient: &Profile,
+        matched: &Profile,
+        match_id: &Uuid,
+        delay: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        let lang = recipient.language().unwrap_or_default();
+        let buttons = vec![
+            InlineKeyboardButton::callback(lang.t(Msg::BtnChat), format!("chat:{match_id}:open")),
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnBlockMatch),
+                format!("block:{}:add", matched.id()),
+            ),
+        ];
+        ProfileCard::new(matched, CardAudience::Match)
+            .with_distance_from(recipient)
+            .with_header(lang.t(Msg::MatchFound))
+            .enqueue(
+                ChatId(*recipient.user_id()),
+                Some(InlineKeyboardMarkup::new(vec![buttons]).into()),
+                Delivery {
+                    delay,
+                    match_id: Some(*match_id),
+                    ..Delivery::default()
+                },
+            )
+    }
+
+    fn block_profile(username: &str, profile_id: Uuid) -> anyhow::Result<()> {
+        let blocker = Profile::get_by_username(username)?.unwrap();
+        ProfileBlock::new(*blocker.id(), profile_id).insert()?;
+        ProfileView::new(*blocker.id(), profile_id).set_action(ViewAction::Skipped)?;
+        Ok(())
+    }
+
+    async fn show_blocked(bot: &Bot, chat_id: ChatId, username: &str, lang: Lang) -> HandlerResult {
+        let blocker = Profile::get_by_username(username)?.unwrap();
+        let blocked = ProfileBlock::blocked_by(blocker.id())?;
+        if blocked.is_empty() {
+            bot.send_message(chat_id, lang.t(Msg::NothingBlocked))
+                .await?;
+            return Ok(());
+        }
+        for profile_id in blocked {
+            let Some(profile) = Profile::get_by_id(&profile_id)? else {
+                continue;
+            };
+            let unblock_button = InlineKeyboardButton::callback(
+                lang.t(Msg::BtnUnblock),
+                format!("block:{profile_id}:remove"),
+            );
+            bot.send_message(
+                chat_id,
+                format!("{}, {}", profile.displayed_name(), profile.age()),
+            )
+            .reply_markup(InlineKeyboardMarkup::new(vec![vec![unblock_button]]))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn open_menu(
+        bot: &Bot,
+        my_dialogue: MyDialogue,
+        chat_id: ChatId,
+        username: &str,
+        lang: Lang,
+    ) -> HandlerResult {
+        my_dialogue
+            .update_dialogue(chat_id, State::ListOptions)
+            .await?;
+        Self::list_options(bot, username, chat_id, lang).await
+    }
+
     pub async fn save_age(
         bot: &Bot,
         my_dialogue: MyDialogue,
         chat_id: ChatId,
         age: i32,
         username: &str,
+        lang: Lang,
     ) -> HandlerResult {
         Profile::update_age(username, age)?;
-        let keyboard = vec![vec![
-            KeyboardButton::new("Я девушка"),
-            KeyboardButton::new("Я парень"),
-        ]];
-        let keyboard_markup = KeyboardMarkup::new(keyboard)
-            .persistent()
-            .resize_keyboard()
-            .selective()
-            .one_time_keyboard();
-        bot.send_message(chat_id, "Теперь определимся с полом")
-            .reply_markup(keyboard_markup)
+        bot.send_message(chat_id, lang.t(Msg::AskGender))
+            .reply_markup(Self::gender_keyboard(lang))
             .await?;
         my_dialogue
             .update_dialogue(chat_id, State::InputGender)
@@ -196,27 +1041,12 @@ impl SwagaBot {
         bot: &Bot,
         my_dialogue: MyDialogue,
         chat_id: ChatId,
-        age: i32,
+        interest: ProfileInterest,
         username: &str,
+        lang: Lang,
     ) -> HandlerResult {
-        Profile::update_age(username, age)?;
-        let keyboard = vec![vec![
-            KeyboardButton::new("Девушки"),
-            KeyboardButton::new("Парни"),
-            KeyboardButton::new("Все равно"),
-        ]];
-        let keyboard_markup = KeyboardMarkup::new(keyboard)
-            .persistent()
-            .resize_keyboard()
-            .selective()
-            .one_time_keyboard();
-        bot.send_message(chat_id, "Из какого ты города?")
-            .reply_markup(keyboard_markup)
-            .await?;
-        my_dialogue
-            .update_dialogue(chat_id, State::InputInterests)
-            .await?;
-        Ok(())
+        Profile::update_interests(username, &interest.to_string())?;
+        Self::open_menu(bot, my_dialogue, chat_id, username, lang).await
     }
 
     pub async fn save_gender(
@@ -225,95 +1055,393 @@ impl SwagaBot {
         chat_id: ChatId,
         gender: ProfileGender,
         username: &str,
+        lang: Lang,
     ) -> HandlerResult {
         Profile::update_gender(username, &gender.to_string())?;
-        bot.send_message(chat_id, "Кто тебе интересен?".to_string())
+        bot.send_message(chat_id, lang.t(Msg::AskInterests))
+            .reply_markup(Self::interests_keyboard(lang))
             .await?;
         my_dialogue
-            .update_dialogue(chat_id, State::InputGender)
+            .update_dialogue(chat_id, State::InputInterests)
             .await?;
         Ok(())
     }
 
-    pub async fn handle_generic_error(
+    /// Stores the largest size of the sent photo as the profile media.
+    async fn save_photo(
         bot: &Bot,
         my_dialogue: MyDialogue,
+        msg: &Message,
+        state: &State,
+        username: &str,
+        lang: Lang,
+        failures: &InputFailures,
+    ) -> HandlerResult {
+        let chat_id = msg.chat.id;
+        let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) else {
+            return Self::reject_input(bot, chat_id, state, username, lang, failures).await;
+        };
+        Profile::update_photo(username, &photo.file.id)?;
+        failures.clear(chat_id);
+        bot.send_message(chat_id, lang.t(Msg::MediaSaved)).await?;
+        Self::open_menu(bot, my_dialogue, chat_id, username, lang).await
+    }
+
+    /// Explains what the current step expects and shows its keyboard again,
+    /// without leaving the step. Repeated failures get `/start` offered.
+    pub async fn reject_input(
+        bot: &Bot,
         chat_id: ChatId,
+        state: &State,
+        username: &str,
+        lang: Lang,
+        failures: &InputFailures,
     ) -> HandlerResult {
-        //todo save to db age
-        bot.send_message(chat_id, "Ты ебанутый че за инпут".to_string())
-            .await?;
-        my_dialogue
-            .update_dialogue(chat_id, State::InputAge)
-            .await?;
+        let (hint, keyboard) = match state {
+            State::InputAge => (Msg::HintAge, None),
+            State::InputMedia => (Msg::HintMedia, None),
+            State::InputDescription => (Msg::HintDescription, None),
+            State::InputGender => (Msg::HintGender, Some(Self::gender_keyboard(lang))),
+            State::InputInterests => (Msg::HintInterests, Some(Self::interests_keyboard(lang))),
+            State::ListOptions => (Msg::HintMenu, Some(Self::menu_keyboard())),
+            State::ViewProfiles { .. } => (Msg::HintSwipe, Some(Self::swipe_keyboard(lang))),
+            State::Start => (Msg::HintStart, None),
+            State::Chatting { .. } => (Msg::HintChat, Some(Self::chat_keyboard(lang))),
+            State::InputSuperlike { .. } => {
+                (Msg::HintSuperlike, Some(Self::superlike_keyboard(lang)))
+            }
+        };
+        let mut text = match state {
+            State::InputAge => lang.f(
+                hint,
+                &[("min", AGE_RANGE.start()), ("max", AGE_RANGE.end())],
+            ),
+            State::InputSuperlike { .. } => lang.f(hint, &[("limit", &NOTE_LIMIT)]),
+            State::InputDescription => lang.f(hint, &[("limit", &DESCRIPTION_LIMIT)]),
+            _ => lang.t(hint).to_string(),
+        };
+        if failures.fail(chat_id) >= ESCAPE_HATCH_AFTER && hint != Msg::HintStart {
+            text = format!("{text}\n\n{}", lang.t(Msg::HintStart));
+        }
+        info!("Invalid input from {} in {:?}", username, state);
+        let request = bot.send_message(chat_id, text);
+        match keyboard {
+            Some(keyboard) => request.reply_markup(keyboard).await?,
+            None => request.await?,
+        };
         Ok(())
     }
 
-    pub async fn dispatcher() {
-        let bot = Bot::from_env();
-        let handler = dptree::entry()
-            .branch(Update::filter_message().endpoint(SwagaBot::message_handler))
-            .branch(Update::filter_callback_query().endpoint(SwagaBot::callback_handler))
-            .branch(Update::filter_inline_query().endpoint(SwagaBot::inline_query_handler));
+    fn gender_keyboard(lang: Lang) -> KeyboardMarkup {
+        let keyboard = vec![vec![
+            KeyboardButton::new(lang.t(Msg::BtnGenderFemale)),
+            KeyboardButton::new(lang.t(Msg::BtnGenderMale)),
+        ]];
+        KeyboardMarkup::new(keyboard)
+            .persistent()
+            .resize_keyboard()
+            .selective()
+            .one_time_keyboard()
+    }
 
-        Dispatcher::builder(bot, handler)
-            .dependencies(dptree::deps![InMemStorage::<State>::new()])
-            .enable_ctrlc_handler()
-            .build()
-            .dispatch()
-            .await;
+    fn interests_keyboard(lang: Lang) -> KeyboardMarkup {
+        let keyboard = vec![vec![
+            KeyboardButton::new(lang.t(Msg::BtnInterestFemale)),
+            KeyboardButton::new(lang.t(Msg::BtnInterestMale)),
+            KeyboardButton::new(lang.t(Msg::BtnInterestAny)),
+        ]];
+        KeyboardMarkup::new(keyboard)
+            .persistent()
+            .resize_keyboard()
+            .selective()
+            .one_time_keyboard()
     }
 
-    async fn list_options(bot: &Bot, username: &str, chat_id: ChatId) -> HandlerResult {
+    fn menu_keyboard() -> KeyboardMarkup {
         let keyboard = vec![vec![
             KeyboardButton::new("1"),
             KeyboardButton::new("2"),
             KeyboardButton::new("3"),
             KeyboardButton::new("4"),
+            KeyboardButton::new("5"),
         ]];
-        let keyboard_markup = KeyboardMarkup::new(keyboard)
+        KeyboardMarkup::new(keyboard)
             .persistent()
             .resize_keyboard()
             .selective()
-            .one_time_keyboard();
-        bot.send_message(chat_id, "Так выглядит твоя анкета")
+            .one_time_keyboard()
+    }
+
+    fn swipe_keyboard(lang: Lang) -> KeyboardMarkup {
+        let keyboard = vec![vec![
+            KeyboardButton::new(lang.t(Msg::BtnLike)),
+            KeyboardButton::new(lang.t(Msg::BtnSuperlike)),
+            KeyboardButton::new(lang.t(Msg::BtnSkip)),
+            KeyboardButton::new(lang.t(Msg::BtnUndo)),
+            KeyboardButton::new(lang.t(Msg::BtnReport)),
+            KeyboardButton::new(lang.t(Msg::BtnBlock)),
+            KeyboardButton::new(lang.t(Msg::BtnMenu)),
+        ]];
+        KeyboardMarkup::new(keyboard)
+            .persistent()
+            .resize_keyboard()
+            .selective()
+    }
+
+    fn superlike_keyboard(lang: Lang) -> KeyboardMarkup {
+        let keyboard = vec![vec![
+            KeyboardButton::new(lang.t(Msg::BtnNoNote)),
+            KeyboardButton::new(lang.t(Msg::BtnCancel)),
+        ]];
+        KeyboardMarkup::new(keyboard)
+            .persistent()
+            .resize_keyboard()
+            .selective()
+    }
+
+    fn chat_keyboard(lang: Lang) -> KeyboardMarkup {
+        let keyboard = vec![vec![
+            KeyboardButton::new(lang.t(Msg::BtnLeaveChat)),
+            KeyboardButton::new(lang.t(Msg::BtnUnmatch)),
+        ]];
+        KeyboardMarkup::new(keyboard)
+            .persistent()
+            .resize_keyboard()
+            .selective()
+    }
+
+    /// Runs the bot until Ctrl-C or SIGTERM, polling or behind a webhook when
+    /// one is configured. On shutdown the handlers in flight finish and the
+    /// outbound queue is flushed.
+    pub async fn dispatcher(config: Config) -> anyhow::Result<()> {
+        let bot = Bot::new(&config.token);
+        if let Err(err) = Self::register_commands(&bot).await {
+            log::error!("Failed to register bot commands: {}", err);
+        }
+        let (stop_tasks, tasks_stopped) = watch::channel(false);
+        let queue = tokio::spawn(OutboundQueue::default().run(bot.clone(), tasks_stopped.clone()));
+        tokio::spawn(Self::prune_relay_messages(tasks_stopped.clone()));
+        let moderation = Arc::new(Moderation::new(
+            config.admin_ids.clone(),
+            config.quotas.report_hide_threshold,
+        ));
+        if config.features.nudges {
+            let nudges = NudgeScheduler::new(moderation.hide_threshold());
+            tokio::spawn(nudges.run(tasks_stopped));
+        }
+        let engine = Arc::new(MatchEngine::new(
+            RecyclePolicy::new(config.quotas.recycle_cooldown_days),
+            moderation.hide_threshold(),
+            config.quotas.liked_you_every,
+        ));
+        let webhook = config.webhook.clone();
+        let handler = dptree::entry()
+            .branch(Update::filter_message().endpoint(SwagaBot::message_handler))
+            .branch(Update::filter_callback_query().endpoint(SwagaBot::callback_handler))
+            .branch(Update::filter_inline_query().endpoint(SwagaBot::inline_query_handler))
+            .branch(Update::filter_my_chat_member().endpoint(SwagaBot::my_chat_member_handler));
+
+        let mut dispatcher = Dispatcher::builder(bot.clone(), handler)
+            .dependencies(dptree::deps![
+                InMemStorage::<State>::new(),
+                engine,
+                moderation,
+                Arc::new(config),
+                Arc::new(InputFailures::default()),
+                Arc::new(SwipeJournal::default())
+            ])
+            .build();
+        let terminate = signal(SignalKind::terminate())?;
+        tokio::spawn(Self::shutdown_on_signal(
+            dispatcher.shutdown_token(),
+            terminate,
+        ));
+        match webhook {
+            Some(webhook) => {
+                let listener = webhook.listen(bot).await?;
+                dispatcher
+                    .dispatch_with_listener(
+                        listener,
+                        LoggingErrorHandler::with_custom_text("An error from the update listener"),
+                    )
+                    .await
+            }
+            None => dispatcher.dispatch().await,
+        }
+        stop_tasks.send(true)?;
+        queue.await?;
+        Ok(())
+    }
+
+    /// Drops relayed messages past the retention period every `PRUNE_EVERY`
+    /// until `stop` flips.
+    async fn prune_relay_messages(mut stop: watch::Receiver<bool>) {
+        let mut ticker = tokio::time::interval(PRUNE_EVERY);
+        while !*stop.borrow() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = stop.changed() => break,
+            }
+            match RelayMessage::prune_expired() {
+                Ok(0) => {}
+                Ok(pruned) => info!("Pruned {} expired relayed messages", pruned),
+                Err(err) => log::error!("Pruning relayed messages failed: {}", err),
+            }
+        }
+    }
+
+    /// Stops the dispatcher gracefully on Ctrl-C or SIGTERM, the signal Docker
+    /// and most process managers stop a service with.
+    async fn shutdown_on_signal(token: ShutdownToken, mut terminate: Signal) {
+        loop {
+            tokio::select! {
+                result = tokio::signal::ctrl_c() => {
+                    if let Err(err) = result {
+                        log::error!("Can't listen for Ctrl-C: {}", err);
+                        return;
+                    }
+                }
+                _ = terminate.recv() => {}
+            }
+            match token.shutdown() {
+                Ok(stopped) => {
+                    info!("Shutdown requested, finishing the updates in flight");
+                    stopped.await;
+                    return;
+                }
+                Err(_) => info!("Shutdown requested before the dispatcher started, ignoring it"),
+            }
+        }
+    }
+
+    async fn list_options(bot: &Bot, username: &str, chat_id: ChatId, lang: Lang) -> HandlerResult {
+        let profile = Profile::get_by_username(username)?.unwrap();
+        let pause_option = match profile.status() {
+            ProfileStatus::Paused => lang.t(Msg::MenuUnpause),
+            _ => lang.t(Msg::MenuPause),
+        };
+        ProfileCard::new(&profile, CardAudience::Owner)
+            .with_header(lang.t(Msg::OwnProfile))
+            .send(bot, chat_id, None)
+            .await?;
+        let mut text = format!("{}\n{pause_option}", lang.t(Msg::MenuOptions));
+        if profile.coordinates().is_none() {
+            text = format!("{text}\n\n{}", lang.t(Msg::HintLocation));
+        }
+        bot.send_message(chat_id, text)
+            .reply_markup(Self::menu_keyboard())
+            .await?;
+        Ok(())
+    }
+
+    async fn toggle_pause(bot: &Bot, chat_id: ChatId, username: &str, lang: Lang) -> HandlerResult {
+        let profile = Profile::get_by_username(username)?.unwrap();
+        let text = match Profile::toggle_pause(profile.id())? {
+            Some(ProfileStatus::Paused) => lang.t(Msg::Paused),
+            Some(_) => lang.t(Msg::Unpaused),
+            None => lang.t(Msg::PauseUnavailable),
+        };
+        bot.send_message(chat_id, text).await?;
+        Self::list_options(bot, username, chat_id, lang).await
+    }
+
+    async fn ask_language(bot: &Bot, chat_id: ChatId, username: &str, lang: Lang) -> HandlerResult {
+        let Some(profile) = Profile::get_by_username(username)? else {
+            bot.send_message(chat_id, lang.t(Msg::NoProfile)).await?;
+            return Ok(());
+        };
+        let buttons = Lang::ALL.map(|choice| {
+            InlineKeyboardButton::callback(
+                choice.native_name(),
+                format!("lang:{}:{choice}", profile.id()),
+            )
+        });
+        bot.send_message(chat_id, lang.t(Msg::AskLanguage))
+            .reply_markup(InlineKeyboardMarkup::new(vec![buttons]))
             .await?;
-        bot.send_message(chat_id, format!("Username {username}"))
+        Ok(())
+    }
+
+    async fn export_data(bot: &Bot, chat_id: ChatId, username: &str, lang: Lang) -> HandlerResult {
+        let Some(profile) = Profile::get_by_username(username)? else {
+            bot.send_message(chat_id, lang.t(Msg::NoProfile)).await?;
+            return Ok(());
+        };
+        let export = DataExport::collect(&profile)?.to_json()?;
+        let document = InputFile::memory(export).file_name("swagadate-export.json");
+        bot.send_document(chat_id, document)
+            .caption(lang.t(Msg::ExportCaption))
+            .await?;
+        Ok(())
+    }
+
+    async fn confirm_delete(
+        bot: &Bot,
+        chat_id: ChatId,
+        username: &str,
+        lang: Lang,
+    ) -> HandlerResult {
+        let Some(profile) = Profile::get_by_username(username)? else {
+            bot.send_message(chat_id, lang.t(Msg::NoProfile)).await?;
+            return Ok(());
+        };
+        let buttons = vec![vec![
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnDeleteConfirm),
+                format!("delete:{}:confirm", profile.id()),
+            ),
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnCancel),
+                format!("delete:{}:cancel", profile.id()),
+            ),
+        ]];
+        bot.send_message(chat_id, lang.t(Msg::DeleteConfirm))
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
             .await?;
-        bot.send_message(chat_id, "1.Смотреть анкеты\n2.Заполнить анкету заново\n3.Изменить фото/видео\n4.Изменить текст анкеты").reply_markup(keyboard_markup).await?;
         Ok(())
     }
 
-    async fn start(bot: Bot, dialogue: &MyDialogue, msg: Message) -> HandlerResult {
+    async fn start(bot: Bot, dialogue: &MyDialogue, msg: Message, lang: Lang) -> HandlerResult {
         if let Some(from) = msg.from {
-            let profile = Profile::new(from.id.0 as i64, from.username);
+            let profile = Profile::new(from.id.0 as i64, from.username, lang);
             let matched_profile = match Profile::get_by_username(profile.username())? {
                 Some(profile) => profile,
                 None => profile.insert()?,
             };
+            // Coming back with /start means the bot is no longer blocked
+            if matched_profile.bot_blocked() {
+                Profile::update_bot_blocked(*matched_profile.user_id(), false)?;
+            }
             let chat_id = ChatId(*matched_profile.user_id());
             dialogue
                 .clone()
                 .update_dialogue(chat_id, State::ListOptions)
                 .await?;
-            Self::send_welcome_message(&bot, matched_profile.username(), chat_id).await?;
-            Self::list_options(&bot, matched_profile.username(), chat_id).await?;
+            Self::send_welcome_message(&bot, matched_profile.username(), chat_id, lang).await?;
+            Self::list_options(&bot, matched_profile.username(), chat_id, lang).await?;
         } else {
             panic!("todo: Msg from must be there");
         }
         Ok(())
     }
 
-    async fn send_welcome_message(bot: &Bot, username: &str, chat_id: ChatId) -> HandlerResult {
+    async fn send_welcome_message(
+        bot: &Bot,
+        username: &str,
+        chat_id: ChatId,
+        lang: Lang,
+    ) -> HandlerResult {
         let db_profile = Profile::get_by_username(username)?;
         match db_profile {
             Some(profile) => {
                 bot.send_message(
                     chat_id,
-                    format!(
-                        "Your user_id={}, username={}",
-                        profile.user_id(),
-                        profile.username()
+                    lang.f(
+                        Msg::Welcome,
+                        &[
+                            ("user_id", profile.user_id()),
+                            ("username", &profile.username()),
+                        ],
                     ),
                 )
                 .await?;
@@ -323,6 +1451,21 @@ impl SwagaBot {
         Ok(())
     }
 
+    /// Tracks users blocking and unblocking the bot in their private chat.
+    async fn my_chat_member_handler(update: ChatMemberUpdated) -> HandlerResult {
+        if !update.chat.is_private() {
+            return Ok(());
+        }
+        let user_id = update.from.id.0 as i64;
+        if update.new_chat_member.is_banned() {
+            info!("User {} blocked the bot", user_id);
+            Profile::update_bot_blocked(user_id, true)?;
+        } else if update.new_chat_member.is_present() {
+            Profile::update_bot_blocked(user_id, false)?;
+        }
+        Ok(())
+    }
+
     async fn inline_query_handler(bot: Bot, q: InlineQuery) -> HandlerResult {
         let choose_debian_version = InlineQueryResultArticle::new(
             "0",
@@ -336,30 +1479,592 @@ impl SwagaBot {
         Ok(())
     }
 
-    /// When it receives a callback from a button it edits the message with all
-    /// those buttons writing a text with the selected Debian version.
+    /// Routes inline button presses. Callback data is `<kind>:<profile_id>:<arg>`.
     ///
     /// **IMPORTANT**: do not send privacy-sensitive data this way!!!
     /// Anyone can read data stored in the callback button.
-    async fn callback_handler(bot: Bot, q: CallbackQuery) -> HandlerResult {
-        if let Some(ref version) = q.data {
-            let text = format!("You chose: {version}");
+    async fn callback_handler(
+        bot: Bot,
+        q: CallbackQuery,
+        dialogue: MyDialogue,
+        engine: Arc<MatchEngine>,
+        moderation: Arc<Moderation>,
+        config: Arc<Config>,
+    ) -> HandlerResult {
+        // Tell telegram that we've seen this query, to remove 🕑 icons from the
+        // clients.
+        bot.answer_callback_query(&q.id).await?;
+        if Profile::get_status_by_user_id(q.from.id.0 as i64)? == Some(ProfileStatus::Banned) {
+            return Ok(());
+        }
+        ActiveDay::record(q.from.id.0 as i64)?;
+        let lang = Self::lang_of(&q.from, config.default_language)?;
+        let Some(ref data) = q.data else {
+            return Ok(());
+        };
+        let mut parts = data.splitn(3, ':');
+        let (Some(kind), Some(profile_id), Some(arg)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Ok(());
+        };
+        let Ok(profile_id) = profile_id.parse::<Uuid>() else {
+            return Ok(());
+        };
+        let text = match kind {
+            "report" => {
+                let Ok(reason) = arg.parse::<ReportReason>() else {
+                    return Ok(());
+                };
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                Self::report_profile(username, profile_id, reason)?;
+                if let Some(message) = q.regular_message() {
+                    bot.edit_text(message, lang.t(Msg::ReportThanks)).await?;
+                }
+                let chat_id = ChatId(q.from.id.0 as i64);
+                return Self::next_profile(&bot, dialogue, chat_id, username, &engine, lang).await;
+            }
+            "block" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                match arg {
+                    "add" => {
+                        Self::block_profile(username, profile_id)?;
+                        lang.t(Msg::Blocked)
+                    }
+                    "remove" => {
+                        let blocker = Profile::get_by_username(username)?.unwrap();
+                        ProfileBlock::new(*blocker.id(), profile_id).delete()?;
+                        lang.t(Msg::Unblocked)
+                    }
+                    _ => return Ok(()),
+                }
+            }
+            "delete" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                // The button must belong to the one pressing it
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                match arg {
+                    "confirm" => {
+                        Profile::delete_with_data(&profile_id)?;
+                        dialogue
+                            .clone()
+                            .remove_dialogue(ChatId(*owner.user_id()))
+                            .await
+                            .ok();
+                        lang.t(Msg::DataDeleted)
+                    }
+                    "cancel" => lang.t(Msg::DeleteCancelled),
+                    _ => return Ok(()),
+                }
+            }
+            // The middle part is the match id here
+            "chat" if arg == "open" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let chat_id = ChatId(q.from.id.0 as i64);
+                return Self::open_chat(&bot, dialogue, chat_id, profile_id, username, lang).await;
+            }
+            // The middle part is the match id here as well
+            "unmatch" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(profile) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                let chat_id = ChatId(q.from.id.0 as i64);
+                let notify = match arg {
+                    "ask" => return Self::ask_unmatch(&bot, chat_id, profile_id, lang).await,
+                    "notify" => true,
+                    "silent" => false,
+                    _ => return Ok(()),
+                };
+                if !Self::unmatch(&profile, &profile_id, notify)? {
+                    lang.t(Msg::ChatUnavailable)
+                } else {
+                    if let Some(State::Chatting { match_id }) =
+                        dialogue.clone().get_dialogue(chat_id).await?
+                        && match_id == profile_id
+                    {
+                        Self::open_menu(&bot, dialogue, chat_id, username, lang).await?;
+                    }
+                    lang.t(Msg::Unmatched)
+                }
+            }
+            "inbox" => {
+                let action = match arg {
+                    "like" => ViewAction::Liked,
+                    "skip" => ViewAction::Skipped,
+                    _ => return Ok(()),
+                };
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(viewer) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                ProfileView::new(*viewer.id(), profile_id).insert()?;
+                Self::rate_profile(username, profile_id, action, false)?;
+                let chat_id = ChatId(q.from.id.0 as i64);
+                // The card may be a photo, so only its buttons are dropped
+                if let Some(message) = q.regular_message() {
+                    bot.edit_message_reply_markup(chat_id, message.id).await?;
+                }
+                return Self::show_likes(&bot, chat_id, username, &engine, lang).await;
+            }
+            "lang" => {
+                let Ok(choice) = arg.parse::<Lang>() else {
+                    return Ok(());
+                };
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                Profile::update_language(&profile_id, choice)?;
+                choice.t(Msg::LanguageChanged)
+            }
+            "privacy" => {
+                let Ok(mode) = arg.parse::<ContactMode>() else {
+                    return Ok(());
+                };
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                Profile::update_contact_mode(&profile_id, mode)?;
+                lang.t(Msg::ContactChanged)
+            }
+            "dnd" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                match Profile::toggle_do_not_disturb(&profile_id)? {
+                    true => lang.t(Msg::NudgesOff),
+                    false => lang.t(Msg::NudgesOn),
+                }
+            }
+            "tz" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                if arg == "ask" {
+                    let buttons: Vec<Vec<InlineKeyboardButton>> = TIMEZONES
+                        .chunks(2)
+                        .map(|row| {
+                            row.iter()
+                                .map(|zone| {
+                                    InlineKeyboardButton::callback(
+                                        zone.name(),
+                                        format!("tz:{profile_id}:{}", zone.name()),
+                                    )
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    bot.send_message(ChatId(q.from.id.0 as i64), lang.t(Msg::AskTimezone))
+                        .reply_markup(InlineKeyboardMarkup::new(buttons))
+                        .await?;
+                    return Ok(());
+                }
+                let Ok(zone) = arg.parse::<Tz>() else {
+                    return Ok(());
+                };
+                Profile::update_timezone(&profile_id, zone)?;
+                lang.t(Msg::TimezoneChanged)
+            }
+            "pause" => {
+                let Some(ref username) = q.from.username else {
+                    return Ok(());
+                };
+                let Some(owner) = Profile::get_by_username(username)? else {
+                    return Ok(());
+                };
+                if *owner.id() != profile_id {
+                    return Ok(());
+                }
+                match Profile::toggle_pause(&profile_id)? {
+                    Some(ProfileStatus::Paused) => lang.t(Msg::Paused),
+                    Some(_) => lang.t(Msg::Unpaused),
+                    None => lang.t(Msg::PauseUnavailable),
+                }
+            }
+            "mod" if moderation.is_admin(q.from.id.0 as i64) => match arg {
+                "approve" => {
+                    ProfileReport::resolve(&profile_id, ReportStatus::Dismissed)?;
+                    lang.t(Msg::ModApproved)
+                }
+                "shadow" => {
+                    ProfileReport::resolve(&profile_id, ReportStatus::Actioned)?;
+                    Profile::update_status(&profile_id, ProfileStatus::ShadowBanned)?;
+                    lang.t(Msg::ModShadowBanned)
+                }
+                "ban" => {
+                    ProfileReport::resolve(&profile_id, ReportStatus::Actioned)?;
+                    Profile::update_status(&profile_id, ProfileStatus::Banned)?;
+                    lang.t(Msg::ModBanned)
+                }
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
 
-            // Tell telegram that we've seen this query, to remove 🕑 icons from the
-            // clients. You could also use `answer_callback_query`'s optional
-            // parameters to tweak what happens on the client side.
-            bot.answer_callback_query(&q.id).await?;
+        if let Some(message) = q.regular_message() {
+            bot.edit_text(message, text).await?;
+        }
+        log::info!("{} on {}: {}", kind, profile_id, arg);
+
+        Ok(())
+    }
 
-            // Edit text of the message to which the buttons were attached
-            if let Some(message) = q.regular_message() {
-                bot.edit_text(message, text).await?;
-            } else if let Some(id) = q.inline_message_id {
-                bot.edit_message_text_inline(id, text).await?;
+    async fn ask_report_reason(
+        bot: &Bot,
+        chat_id: ChatId,
+        profile_id: Uuid,
+        lang: Lang,
+    ) -> HandlerResult {
+        let buttons = ReportReason::ALL.map(|reason| {
+            let label = lang.t(match reason {
+                ReportReason::Spam => Msg::ReasonSpam,
+                ReportReason::Fake => Msg::ReasonFake,
+                ReportReason::Underage => Msg::ReasonUnderage,
+                ReportReason::Offensive => Msg::ReasonOffensive,
+            });
+            vec![InlineKeyboardButton::callback(
+                label,
+                format!("report:{profile_id}:{reason}"),
+            )]
+        });
+        bot.send_message(chat_id, lang.t(Msg::AskReportReason))
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
+            .await?;
+        Ok(())
+    }
+
+    fn report_profile(
+        username: &str,
+        profile_id: Uuid,
+        reason: ReportReason,
+    ) -> anyhow::Result<()> {
+        let reporter = Profile::get_by_username(username)?.unwrap();
+        ProfileReport::new(*reporter.id(), profile_id, reason).insert()?;
+        ProfileView::new(*reporter.id(), profile_id).set_action(ViewAction::Reported)?;
+        Ok(())
+    }
+
+    async fn show_mod_queue(bot: &Bot, chat_id: ChatId, lang: Lang) -> HandlerResult {
+        let queue = ProfileReport::open_queue(MOD_QUEUE_PAGE)?;
+        if queue.is_empty() {
+            bot.send_message(chat_id, lang.t(Msg::ModQueueEmpty))
+                .await?;
+            return Ok(());
+        }
+        for (profile_id, reports) in queue {
+            let Some(profile) = Profile::get_by_id(&profile_id)? else {
+                // Already banned or deleted, nothing left to decide
+                ProfileReport::resolve(&profile_id, ReportStatus::Actioned)?;
+                continue;
+            };
+            let reasons = ProfileReport::open_reasons(&profile_id)?
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let header = lang.f(
+                Msg::ModReports,
+                &[("count", &reports), ("reasons", &reasons)],
+            );
+            let buttons = vec![vec![
+                InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnModApprove),
+                    format!("mod:{profile_id}:approve"),
+                ),
+                InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnModShadowBan),
+                    format!("mod:{profile_id}:shadow"),
+                ),
+                InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnModBan),
+                    format!("mod:{profile_id}:ban"),
+                ),
+            ]];
+            ProfileCard::new(&profile, CardAudience::Moderator)
+                .with_header(header)
+                .send(
+                    bot,
+                    chat_id,
+                    Some(InlineKeyboardMarkup::new(buttons).into()),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn show_stats(bot: &Bot, chat_id: ChatId, lang: Lang) -> HandlerResult {
+        let stats = Stats::collect()?;
+        let join = |rows: &[(String, i64)]| {
+            rows.iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let text = lang.f(
+            Msg::AdminStats,
+            &[
+                ("active", &stats.active_today),
+                ("profiles_today", &stats.profiles_today),
+                ("profiles_week", &stats.profiles_week),
+                ("likes_today", &stats.likes_today),
+                ("likes_week", &stats.likes_week),
+                ("matches_week", &stats.matches_week),
+                ("match_rate", &format!("{:.1}", stats.match_rate())),
+                ("genders", &join(&stats.genders)),
+                ("onboarding", &join(&stats.onboarding)),
+            ],
+        );
+        bot.send_message(chat_id, text).await?;
+        Ok(())
+    }
+
+    async fn inspect_user(bot: &Bot, chat_id: ChatId, query: &str, lang: Lang) -> HandlerResult {
+        if query.trim().is_empty() {
+            bot.send_message(chat_id, lang.t(Msg::AdminUserUsage))
+                .await?;
+            return Ok(());
+        }
+        let Some(profile) = Profile::find_for_admin(query)? else {
+            bot.send_message(chat_id, lang.t(Msg::AdminUserNotFound))
+                .await?;
+            return Ok(());
+        };
+        let (likes_given, likes_received) = ProfileLike::counts_for(profile.id())?;
+        let header = lang.f(
+            Msg::AdminUserInfo,
+            &[
+                ("status", &profile.status()),
+                ("created_at", &profile.created_at().format("%Y-%m-%d %H:%M")),
+                ("likes_given", &likes_given),
+                ("likes_received", &likes_received),
+                ("matches", &ProfileMatch::history_for(profile.id())?.len()),
+                ("reports", &ProfileReport::counted_for(profile.id())?),
+            ],
+        );
+        ProfileCard::new(&profile, CardAudience::Moderator)
+            .with_header(header)
+            .send(bot, chat_id, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Queues the text for everyone, the outbound queue paces the delivery.
+    async fn broadcast(bot: &Bot, chat_id: ChatId, text: String, lang: Lang) -> HandlerResult {
+        let text = text.trim();
+        if text.is_empty() {
+            bot.send_message(chat_id, lang.t(Msg::AdminBroadcastUsage))
+                .await?;
+            return Ok(());
+        }
+        let recipients = Profile::get_broadcast_user_ids()?;
+        OutboundJob::enqueue_text_for_all(&recipients, &html::escape(text))?;
+        bot.send_message(
+            chat_id,
+            lang.f(Msg::AdminBroadcastQueued, &[("count", &recipients.len())]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Puts the user into the relay chat with a match, if they are part of it.
+    async fn open_chat(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        chat_id: ChatId,
+        match_id: Uuid,
+        username: &str,
+        lang: Lang,
+    ) -> HandlerResult {
+        let Some(profile) = Profile::get_by_username(username)? else {
+            bot.send_message(chat_id, lang.t(Msg::NeedStart)).await?;
+            return Ok(());
+        };
+        let Some(matched) = Self::chat_partner(&profile, &match_id)? else {
+            bot.send_message(chat_id, lang.t(Msg::ChatUnavailable))
+                .await?;
+            return Ok(());
+        };
+        dialogue
+            .update_dialogue(chat_id, State::Chatting { match_id })
+            .await?;
+        bot.send_message(
+            chat_id,
+            lang.f(Msg::ChatOpened, &[("name", &short_label(&matched))]),
+        )
+        .reply_markup(Self::chat_keyboard(lang))
+        .await?;
+        Ok(())
+    }
+
+    /// The other side of the match, unless the match is gone or either side
+    /// has blocked the other.
+    fn chat_partner(profile: &Profile, match_id: &Uuid) -> anyhow::Result<Option<Profile>> {
+        let Some(profile_match) = ProfileMatch::get(match_id)? else {
+            return Ok(None);
+        };
+        if !profile_match.includes(profile.id()) || !profile_match.is_active() {
+            return Ok(None);
+        }
+        let other = profile_match.other(profile.id());
+        if ProfileBlock::exists_between(profile.id(), &other)? {
+            return Ok(None);
+        }
+        Profile::get_by_id(&other)
+    }
+
+    /// Forwards a text, photo or voice message to the other side of the match
+    /// under the sender's displayed name, through the outbound queue.
+    async fn relay_message(
+        bot: &Bot,
+        dialogue: MyDialogue,
+        msg: &Message,
+        match_id: Uuid,
+        username: &str,
+        lang: Lang,
+    ) -> HandlerResult {
+        let chat_id = msg.chat.id;
+        if let Some(text) = msg.text() {
+            match Msg::from_button(text, &[Msg::BtnLeaveChat, Msg::BtnUnmatch]) {
+                Some(Msg::BtnLeaveChat) => {
+                    return Self::open_menu(bot, dialogue, chat_id, username, lang).await;
+                }
+                Some(_) => return Self::ask_unmatch(bot, chat_id, match_id, lang).await,
+                None => {}
             }
+        }
+        let sender = Profile::get_by_username(username)?.unwrap();
+        let Some(recipient) =
+            Self::chat_partner(&sender, &match_id)?.filter(|recipient| !recipient.bot_blocked())
+        else {
+            bot.send_message(chat_id, lang.t(Msg::ChatUnavailable))
+                .await?;
+            return Self::open_menu(bot, dialogue, chat_id, username, lang).await;
+        };
+        let (kind, body, file_id) = if let Some(text) = msg.text() {
+            (RelayKind::Text, Some(text.to_string()), None)
+        } else if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+            let caption = msg.caption().map(String::from);
+            (RelayKind::Photo, caption, Some(photo.file.id.to_string()))
+        } else if let Some(voice) = msg.voice() {
+            let caption = msg.caption().map(String::from);
+            (RelayKind::Voice, caption, Some(voice.file.id.to_string()))
+        } else {
+            bot.send_message(chat_id, lang.t(Msg::RelayUnsupported))
+                .await?;
+            return Ok(());
+        };
+        RelayMessage::new(match_id, *sender.id(), kind, body.clone(), file_id.clone()).insert()?;
+        ProfileMatch::touch(&match_id)?;
 
-            log::info!("You chose: {}", version);
+        let recipient_lang = recipient.language().unwrap_or_default();
+        let name = match sender.displayed_name().is_empty() {
+            true => recipient_lang.t(Msg::YourMatch),
+            false => sender.displayed_name(),
+        };
+        let mut text = html::bold(&html::escape(name));
+        if let Some(body) = body {
+            text = format!("{text}: {}", html::escape(&body));
         }
+        let reply = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+            recipient_lang.t(Msg::BtnReply),
+            format!("chat:{match_id}:open"),
+        )]]);
+        let recipient_chat = ChatId(*recipient.user_id());
+        // Deleting the sender's profile drops what wasn't delivered yet
+        let delivery = Delivery {
+            subject_id: Some(*sender.id()),
+            ..Delivery::default()
+        };
+        let markup = Some(reply.into());
+        match (kind, file_id) {
+            (RelayKind::Photo, Some(file_id)) => {
+                OutboundQueue::photo(recipient_chat, &file_id, text, markup, delivery)?
+            }
+            (RelayKind::Voice, Some(file_id)) => {
+                OutboundQueue::voice(recipient_chat, &file_id, Some(text), markup, delivery)?
+            }
+            _ => OutboundQueue::text(recipient_chat, text, markup, delivery)?,
+        }
+        Ok(())
+    }
 
+    async fn ask_unmatch(bot: &Bot, chat_id: ChatId, match_id: Uuid, lang: Lang) -> HandlerResult {
+        let buttons = vec![vec![
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnUnmatchNotify),
+                format!("unmatch:{match_id}:notify"),
+            ),
+            InlineKeyboardButton::callback(
+                lang.t(Msg::BtnUnmatchSilent),
+                format!("unmatch:{match_id}:silent"),
+            ),
+        ]];
+        bot.send_message(chat_id, lang.t(Msg::UnmatchConfirm))
+            .reply_markup(InlineKeyboardMarkup::new(buttons))
+            .await?;
         Ok(())
     }
+
+    /// Ends an active match on behalf of the profile. The other side is told
+    /// about it through the outbound queue unless the unmatch is silent.
+    /// Returns whether anything changed.
+    fn unmatch(profile: &Profile, match_id: &Uuid, notify: bool) -> anyhow::Result<bool> {
+        let Some(profile_match) = ProfileMatch::get(match_id)? else {
+            return Ok(false);
+        };
+        if !profile_match.includes(profile.id()) || !profile_match.is_active() {
+            return Ok(false);
+        }
+        profile_match.unmatch(profile.id())?;
+        if notify && let Some(other) = Profile::get_by_id(&profile_match.other(profile.id()))? {
+            let other_lang = other.language().unwrap_or_default();
+            OutboundQueue::text(
+                ChatId(*other.user_id()),
+                html::escape(other_lang.t(Msg::UnmatchedByOther)),
+                None,
+                Delivery::default(),
+            )?;
+        }
+        Ok(true)
+    }
 }
@@ -0,0 +1,132 @@
+use crate::profile::Profile;
+use crate::profile_like::ProfileLike;
+use crate::profile_match::ProfileMatch;
+use crate::profile_report::ProfileReport;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Everything the bot stores about a user, as handed out by `/export`.
+#[derive(Serialize)]
+pub struct DataExport {
+    profile: ExportedProfile,
+    likes_given: Vec<Uuid>,
+    matches: Vec<Uuid>,
+    reports: Vec<ExportedReport>,
+}
+
+#[derive(Serialize)]
+struct ExportedProfile {
+    id: Uuid,
+    user_id: i64,
+    username: String,
+    created_at: NaiveDateTime,
+    displayed_name: String,
+    description: String,
+    age: i32,
+    location: String,
+    gender: String,
+    interests: String,
+    status: String,
+    contact_mode: String,
+    do_not_disturb: bool,
+    timezone: String,
+    file_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ExportedReport {
+    profile_id: Uuid,
+    reason: String,
+    status: String,
+    created_at: NaiveDateTime,
+}
+
+impl DataExport {
+    /// Assembles the export from already loaded data, no database involved.
+    pub fn new(
+        profile: &Profile,
+        likes_given: Vec<Uuid>,
+        matches: Vec<Uuid>,
+        reports: Vec<(Uuid, String, String, NaiveDateTime)>,
+    ) -> Self {
+        Self {
+            profile: ExportedProfile {
+                id: *profile.id(),
+                user_id: *profile.user_id(),
+                username: profile.username().to_string(),
+                created_at: *profile.created_at(),
+                displayed_name: profile.displayed_name().to_string(),
+                description: profile.description().to_string(),
+                age: *profile.age(),
+                location: profile.location().to_string(),
+                gender: profile.gender().to_string(),
+                interests: profile.interests().to_string(),
+                status: profile.status().to_string(),
+                contact_mode: profile.contact_mode().to_string(),
+                do_not_disturb: profile.do_not_disturb(),
+                timezone: profile.timezone().name().to_string(),
+                file_ids: profile.file_ids().into_iter().map(String::from).collect(),
+            },
+            likes_given,
+            matches,
+            reports: reports
+                .into_iter()
+                .map(|(profile_id, reason, status, created_at)| ExportedReport {
+                    profile_id,
+                    reason,
+                    status,
+                    created_at,
+                })
+                .collect(),
+        }
+    }
+
+    /// Loads the export for a profile from the database.
+    pub fn collect(profile: &Profile) -> anyhow::Result<Self> {
+        Ok(Self::new(
+            profile,
+            ProfileLike::liked_by(profile.id())?,
+            ProfileMatch::history_for(profile.id())?
+                .iter()
+                .map(|profile_match| profile_match.other(profile.id()))
+                .collect(),
+            ProfileReport::filed_by(profile.id())?,
+        ))
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+    use serde_json::Value;
+
+    #[test]
+    fn export_has_every_section() {
+        let profile = Profile::new(42, Some("alice".to_string()), Lang::En);
+        let liked = Uuid::new_v4();
+        let matched = Uuid::new_v4();
+        let reported = Uuid::new_v4();
+        let created_at = NaiveDateTime::default();
+        let export = DataExport::new(
+            &profile,
+            vec![liked],
+            vec![matched],
+            vec![(reported, "SPAM".to_string(), "OPEN".to_string(), created_at)],
+        );
+
+        let json: Value = serde_json::from_slice(&export.to_json().unwrap()).unwrap();
+        assert_eq!(json["profile"]["user_id"], 42);
+        assert_eq!(json["profile"]["username"], "alice");
+        assert_eq!(json["profile"]["file_ids"], Value::Array(vec![]));
+        assert_eq!(json["likes_given"][0], liked.to_string());
+        assert_eq!(json["matches"][0], matched.to_string());
+        assert_eq!(json["reports"][0]["profile_id"], reported.to_string());
+        assert_eq!(json["reports"][0]["reason"], "SPAM");
+    }
+}
@@ -0,0 +1,647 @@
+use diesel::result::Error as DieselError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Languages the bot speaks. The first one is the fallback.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    Ru,
+    En,
+}
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lang::Ru => write!(f, "ru"),
+            Lang::En => write!(f, "en"),
+        }
+    }
+}
+impl FromStr for Lang {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lang = match s {
+            "ru" => Self::Ru,
+            "en" => Self::En,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(lang)
+    }
+}
+
+/// Keys of the message catalog. Every user-facing string goes through one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Msg {
+    AccountBanned,
+    CommandNotFound,
+    Welcome,
+    AskAge,
+    AskGender,
+    AskInterests,
+    AskMedia,
+    AskDescription,
+    MediaSaved,
+    DescriptionSaved,
+    HintAge,
+    HintGender,
+    HintInterests,
+    HintMenu,
+    HintSwipe,
+    HintStart,
+    HintChat,
+    HintSuperlike,
+    HintMedia,
+    HintDescription,
+    OwnProfile,
+    DistanceKm,
+    HintLocation,
+    LocationSaved,
+    MenuOptions,
+    MenuPause,
+    MenuUnpause,
+    Paused,
+    Unpaused,
+    PauseUnavailable,
+    RecycledProfiles,
+    ProfilesExhausted,
+    MatchFound,
+    NothingBlocked,
+    Blocked,
+    Unblocked,
+    NoProfile,
+    ExportCaption,
+    DeleteConfirm,
+    DataDeleted,
+    DeleteCancelled,
+    AskReportReason,
+    ReportThanks,
+    ReasonSpam,
+    ReasonFake,
+    ReasonUnderage,
+    ReasonOffensive,
+    ModQueueEmpty,
+    ModReports,
+    AdminStats,
+    AdminUserUsage,
+    AdminUserNotFound,
+    AdminUserInfo,
+    AdminBroadcastUsage,
+    AdminBroadcastQueued,
+    ModApproved,
+    ModShadowBanned,
+    ModBanned,
+    AskLanguage,
+    LanguageChanged,
+    NeedStart,
+    MatchesEmpty,
+    MatchesHeader,
+    Settings,
+    SettingsVisible,
+    SettingsHidden,
+    ContactUsername,
+    ContactDeepLink,
+    ContactRelay,
+    ContactRelayHint,
+    ContactChanged,
+    ChatOpened,
+    ChatUnavailable,
+    RelayUnsupported,
+    YourMatch,
+    Unmatched,
+    UnmatchedByOther,
+    UnmatchConfirm,
+    SettingsNudgesOn,
+    SettingsNudgesOff,
+    NudgesOn,
+    NudgesOff,
+    AskTimezone,
+    TimezoneChanged,
+    NudgeIncomplete,
+    NudgeLike,
+    NudgeMatch,
+    LikesHeader,
+    LikesEmpty,
+    AskSuperlikeNote,
+    SuperlikeLimit,
+    SuperlikeCancelled,
+    SuperlikeSent,
+    SuperlikeAlreadySent,
+    SuperlikeReceived,
+    UndoDone,
+    UndoUnavailable,
+    CmdStart,
+    CmdProfile,
+    CmdBrowse,
+    CmdEdit,
+    CmdMatches,
+    CmdLikes,
+    CmdSettings,
+    CmdPause,
+    CmdBlocked,
+    CmdLanguage,
+    CmdExport,
+    CmdDelete,
+    CmdCancel,
+    BtnGenderFemale,
+    BtnGenderMale,
+    BtnInterestFemale,
+    BtnInterestMale,
+    BtnInterestAny,
+    BtnLike,
+    BtnSuperlike,
+    BtnUndo,
+    BtnNoNote,
+    BtnSkip,
+    BtnReport,
+    BtnBlock,
+    BtnMenu,
+    BtnBlockMatch,
+    BtnUnblock,
+    BtnDeleteConfirm,
+    BtnCancel,
+    BtnTogglePause,
+    BtnChat,
+    BtnChatWith,
+    BtnReply,
+    BtnLeaveChat,
+    BtnUnmatch,
+    BtnUnmatchNotify,
+    BtnUnmatchSilent,
+    BtnToggleNudges,
+    BtnTimezone,
+    BtnModApprove,
+    BtnModShadowBan,
+    BtnModBan,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::Ru, Lang::En];
+
+    /// Picks the language from Telegram's `User::language_code`, `fallback`
+    /// when the client doesn't tell.
+    pub fn from_code(code: Option<&str>, fallback: Lang) -> Self {
+        match code.map(|code| code.split(['-', '_']).next().unwrap_or(code)) {
+            Some("en") => Lang::En,
+            Some("ru" | "uk" | "be" | "kk") => Lang::Ru,
+            Some(_) => Lang::En,
+            None => fallback,
+        }
+    }
+
+    /// Name of the language in that language, for the `/language` picker.
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Lang::Ru => "Русский",
+            Lang::En => "English",
+        }
+    }
+
+    pub fn t(&self, msg: Msg) -> &'static str {
+        match self {
+            Lang::Ru => ru(msg),
+            Lang::En => en(msg),
+        }
+    }
+
+    /// Translates and fills the `{name}` placeholders of the message.
+    pub fn f(&self, msg: Msg, args: &[(&str, &(dyn fmt::Display + Sync))]) -> String {
+        args.iter()
+            .fold(self.t(msg).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), &value.to_string())
+            })
+    }
+}
+
+impl Msg {
+    /// Resolves the text of a keyboard button back to its key, in any language.
+    pub fn from_button(text: &str, buttons: &[Msg]) -> Option<Msg> {
+        buttons
+            .iter()
+            .copied()
+            .find(|button| Lang::ALL.iter().any(|lang| lang.t(*button) == text))
+    }
+}
+
+fn ru(msg: Msg) -> &'static str {
+    match msg {
+        Msg::AccountBanned => "Твой аккаунт заблокирован модератором",
+        Msg::CommandNotFound => "Нет такой команды!",
+        Msg::Welcome => "Твой user_id={user_id}, username={username}",
+        Msg::AskAge => "Сколько тебе лет?",
+        Msg::AskGender => "Теперь определимся с полом",
+        Msg::AskInterests => "Кто тебе интересен?",
+        Msg::AskMedia => "Пришли фото для анкеты",
+        Msg::AskDescription => "Расскажи о себе, до {limit} символов",
+        Msg::MediaSaved => "Фото обновлено",
+        Msg::DescriptionSaved => "Текст анкеты обновлён",
+        Msg::HintAge => "Напиши возраст числом от {min} до {max}",
+        Msg::HintGender => "Выбери пол кнопкой ниже",
+        Msg::HintInterests => "Выбери кнопкой ниже, кто тебе интересен",
+        Msg::HintMenu => "Выбери пункт меню: отправь цифру от 1 до 5",
+        Msg::HintSwipe => {
+            "Оцени анкету кнопками ниже: ❤️ нравится, 👎 дальше, 🚩 пожаловаться, 🚫 заблокировать, 💤 в меню"
+        }
+        Msg::HintStart => "Что-то не получается? Нажми /start, чтобы начать заново",
+        Msg::HintChat => "Пришли текст, фото или голосовое, или выбери кнопку ниже",
+        Msg::HintSuperlike => "Пришли текст до {limit} символов, голосовое или выбери кнопку ниже",
+        Msg::HintMedia => "Пришли одно фото",
+        Msg::HintDescription => "Пришли текст до {limit} символов",
+        Msg::OwnProfile => "Так выглядит твоя анкета",
+        Msg::DistanceKm => "{km} км от тебя",
+        Msg::HintLocation => "📍 Пришли геопозицию, чтобы видеть, как далеко анкеты",
+        Msg::LocationSaved => "Геопозиция сохранена, теперь на анкетах видно расстояние",
+        Msg::MenuOptions => {
+            "1.Смотреть анкеты\n2.Заполнить анкету заново\n3.Изменить фото\n4.Изменить текст анкеты"
+        }
+        Msg::MenuPause => "5.Скрыть мою анкету",
+        Msg::MenuUnpause => "5.Снова показывать мою анкету",
+        Msg::Paused => "Анкета скрыта, тебя никто не увидит. Смотреть анкеты можно как раньше",
+        Msg::Unpaused => "Анкета снова видна другим",
+        Msg::PauseUnavailable => "Сейчас это недоступно",
+        Msg::RecycledProfiles => {
+            "Ты посмотрел всех рядом, показываем анкеты, которые ты пропустил раньше"
+        }
+        Msg::ProfilesExhausted => "Анкеты закончились, загляни попозже",
+        Msg::MatchFound => "Есть взаимная симпатия!",
+        Msg::NothingBlocked => "Ты никого не блокировал",
+        Msg::Blocked => "🚫 Заблокирован, вы больше не увидите друг друга",
+        Msg::Unblocked => "Разблокирован",
+        Msg::NoProfile => "У тебя нет анкеты",
+        Msg::ExportCaption => "Все данные, которые мы о тебе храним",
+        Msg::DeleteConfirm => "Удалить анкету, лайки, просмотры и фото? Это нельзя отменить",
+        Msg::DataDeleted => "Все данные удалены. Чтобы вернуться, нажми /start",
+        Msg::DeleteCancelled => "Удаление отменено",
+        Msg::AskReportReason => "Что не так с анкетой?",
+        Msg::ReportThanks => "Спасибо, мы проверим эту анкету",
+        Msg::ReasonSpam => "Спам",
+        Msg::ReasonFake => "Фейк",
+        Msg::ReasonUnderage => "Несовершеннолетний",
+        Msg::ReasonOffensive => "Оскорбительный контент",
+        Msg::ModQueueEmpty => "Очередь модерации пуста",
+        Msg::ModReports => "Жалоб: {count} ({reasons})",
+        Msg::AdminStats => {
+            "Статистика\n\
+            Активных сегодня: {active}\n\
+            Новых анкет: {profiles_today} сегодня, {profiles_week} за неделю\n\
+            Лайков: {likes_today} сегодня, {likes_week} за неделю\n\
+            Мэтчей за неделю: {matches_week} ({match_rate}% лайков)\n\
+            Пол: {genders}\n\
+            Онбординг: {onboarding}"
+        }
+        Msg::AdminUserUsage => "Использование: /user <telegram id, @username или id анкеты>",
+        Msg::AdminUserNotFound => "Анкета не найдена",
+        Msg::AdminUserInfo => {
+            "Статус: {status}\n\
+            Создана: {created_at}\n\
+            Лайков: {likes_given} поставлено, {likes_received} получено\n\
+            Мэтчей: {matches}\n\
+            Жалоб: {reports}"
+        }
+        Msg::AdminBroadcastUsage => "Использование: /broadcast <текст>",
+        Msg::AdminBroadcastQueued => "Рассылка на {count} пользователей поставлена в очередь",
+        Msg::ModApproved => "✅ Жалобы отклонены",
+        Msg::ModShadowBanned => "👻 Анкета скрыта из выдачи",
+        Msg::ModBanned => "⛔ Анкета заблокирована",
+        Msg::AskLanguage => "Выбери язык",
+        Msg::LanguageChanged => "Теперь я говорю по-русски",
+        Msg::NeedStart => "Сначала создай анкету: нажми /start",
+        Msg::MatchesEmpty => "Взаимных симпатий пока нет",
+        Msg::MatchesHeader => "Твои взаимные симпатии:",
+        Msg::Settings => {
+            "Настройки\nЯзык: {language}\nАнкета: {visibility}\nКонтакт для пар: {contact}\nЧасовой пояс: {timezone}\nНапоминания: {nudges}"
+        }
+        Msg::SettingsVisible => "видна другим",
+        Msg::SettingsHidden => "скрыта",
+        Msg::ContactUsername => "@username",
+        Msg::ContactDeepLink => "Ссылка на аккаунт",
+        Msg::ContactRelay => "Только через бота",
+        Msg::ContactRelayHint => "общение через бота",
+        Msg::ContactChanged => "Настройки приватности сохранены",
+        Msg::ChatOpened => {
+            "Чат с {name}. Текст, фото и голосовые я перешлю от твоего имени, контакты останутся скрыты"
+        }
+        Msg::ChatUnavailable => "Этот чат больше недоступен",
+        Msg::RelayUnsupported => "Пересылаю только текст, фото и голосовые",
+        Msg::YourMatch => "Твоя пара",
+        Msg::Unmatched => "Пара удалена",
+        Msg::UnmatchedByOther => "Одна из твоих пар удалила тебя из взаимных симпатий",
+        Msg::UnmatchConfirm => "Удалить пару? Чат закроется, и вы больше не увидите друг друга",
+        Msg::SettingsNudgesOn => "включены",
+        Msg::SettingsNudgesOff => "не беспокоить",
+        Msg::NudgesOn => "Напоминания включены",
+        Msg::NudgesOff => "Больше не буду беспокоить напоминаниями",
+        Msg::AskTimezone => "Выбери часовой пояс, ночью я не буду присылать напоминания",
+        Msg::TimezoneChanged => "Часовой пояс сохранён",
+        Msg::NudgeIncomplete => "Анкета заполнена не до конца. Нажми /edit, это займёт минуту",
+        Msg::NudgeLike => "Кто-то лайкнул твою анкету! Загляни в /browse",
+        Msg::NudgeMatch => "{name} ждёт твоего сообщения",
+        Msg::LikesHeader => "Тебя лайкнули: {count}. Ответь взаимностью или пропусти",
+        Msg::LikesEmpty => "Новых лайков пока нет",
+        Msg::AskSuperlikeNote => {
+            "Суперлайк придёт сразу же. Добавь сообщение до {limit} символов или голосовое"
+        }
+        Msg::SuperlikeLimit => "Суперлайки на сегодня закончились, завтра будут новые",
+        Msg::SuperlikeCancelled => "Суперлайк отменён",
+        Msg::SuperlikeSent => "Суперлайк отправлен ⭐",
+        Msg::SuperlikeAlreadySent => "Ты уже отправлял(а) суперлайк этой анкете",
+        Msg::SuperlikeReceived => "⭐ Тебе суперлайк!",
+        Msg::UndoDone => "Отменил, вот эта анкета снова",
+        Msg::UndoUnavailable => "Отменить уже нельзя",
+        Msg::CmdStart => "Начать заново",
+        Msg::CmdProfile => "Моя анкета и меню",
+        Msg::CmdBrowse => "Смотреть анкеты",
+        Msg::CmdEdit => "Заполнить анкету заново",
+        Msg::CmdMatches => "Взаимные симпатии",
+        Msg::CmdLikes => "Кто меня лайкнул",
+        Msg::CmdSettings => "Настройки",
+        Msg::CmdPause => "Скрыть или показать анкету",
+        Msg::CmdBlocked => "Заблокированные",
+        Msg::CmdLanguage => "Сменить язык",
+        Msg::CmdExport => "Выгрузить мои данные",
+        Msg::CmdDelete => "Удалить анкету и все данные",
+        Msg::CmdCancel => "Отменить и вернуться в меню",
+        Msg::BtnGenderFemale => "Я девушка",
+        Msg::BtnGenderMale => "Я парень",
+        Msg::BtnInterestFemale => "Девушки",
+        Msg::BtnInterestMale => "Парни",
+        Msg::BtnInterestAny => "Все равно",
+        Msg::BtnLike => "❤️",
+        Msg::BtnSuperlike => "⭐",
+        Msg::BtnUndo => "↩️",
+        Msg::BtnNoNote => "Отправить без сообщения",
+        Msg::BtnSkip => "👎",
+        Msg::BtnReport => "🚩",
+        Msg::BtnBlock => "🚫",
+        Msg::BtnMenu => "💤",
+        Msg::BtnBlockMatch => "🚫 Заблокировать",
+        Msg::BtnUnblock => "Разблокировать",
+        Msg::BtnDeleteConfirm => "Да, удалить всё",
+        Msg::BtnCancel => "Отмена",
+        Msg::BtnTogglePause => "Скрыть / показать анкету",
+        Msg::BtnChat => "Написать через бота",
+        Msg::BtnChatWith => "💬 {name}",
+        Msg::BtnReply => "Ответить",
+        Msg::BtnLeaveChat => "Выйти из чата",
+        Msg::BtnUnmatch => "Удалить пару",
+        Msg::BtnUnmatchNotify => "Удалить и сообщить",
+        Msg::BtnUnmatchSilent => "Удалить молча",
+        Msg::BtnToggleNudges => "Напоминания вкл/выкл",
+        Msg::BtnTimezone => "Часовой пояс",
+        Msg::BtnModApprove => "✅ Оставить",
+        Msg::BtnModShadowBan => "👻 Скрыть",
+        Msg::BtnModBan => "⛔ Забанить",
+    }
+}
+
+fn en(msg: Msg) -> &'static str {
+    match msg {
+        Msg::AccountBanned => "Your account has been banned by a moderator",
+        Msg::CommandNotFound => "Command not found!",
+        Msg::Welcome => "Your user_id={user_id}, username={username}",
+        Msg::AskAge => "How old are you?",
+        Msg::AskGender => "Now, what's your gender?",
+        Msg::AskInterests => "Who are you interested in?",
+        Msg::AskMedia => "Send a photo for your profile",
+        Msg::AskDescription => "Tell something about yourself, up to {limit} characters",
+        Msg::MediaSaved => "Photo updated",
+        Msg::DescriptionSaved => "Profile text updated",
+        Msg::HintAge => "Please enter your age as a number between {min} and {max}",
+        Msg::HintGender => "Please pick your gender with the buttons below",
+        Msg::HintInterests => "Please pick who you're interested in with the buttons below",
+        Msg::HintMenu => "Please choose a menu item: send a number from 1 to 5",
+        Msg::HintSwipe => {
+            "Rate the profile with the buttons below: ❤️ like, 👎 next, 🚩 report, 🚫 block, 💤 menu"
+        }
+        Msg::HintStart => "Stuck? Press /start to begin again",
+        Msg::HintChat => "Send a text, photo or voice note, or use the buttons below",
+        Msg::HintSuperlike => {
+            "Send a text up to {limit} characters, a voice note, or use the buttons below"
+        }
+        Msg::HintMedia => "Please send a single photo",
+        Msg::HintDescription => "Please send a text up to {limit} characters",
+        Msg::OwnProfile => "This is how your profile looks",
+        Msg::DistanceKm => "{km} km away",
+        Msg::HintLocation => "📍 Share your location to see how far away profiles are",
+        Msg::LocationSaved => "Location saved, profiles now show the distance",
+        Msg::MenuOptions => {
+            "1.Browse profiles\n2.Fill in the profile again\n3.Change photo\n4.Change profile text"
+        }
+        Msg::MenuPause => "5.Hide my profile",
+        Msg::MenuUnpause => "5.Show my profile again",
+        Msg::Paused => "Your profile is hidden, nobody will see it. You can still browse",
+        Msg::Unpaused => "Your profile is visible again",
+        Msg::PauseUnavailable => "This is not available right now",
+        Msg::RecycledProfiles => {
+            "You've seen everyone nearby, showing profiles you skipped earlier again"
+        }
+        Msg::ProfilesExhausted => "No more profiles, check back later",
+        Msg::MatchFound => "It's a match!",
+        Msg::NothingBlocked => "You haven't blocked anyone",
+        Msg::Blocked => "🚫 Blocked, you won't see each other again",
+        Msg::Unblocked => "Unblocked",
+        Msg::NoProfile => "You don't have a profile",
+        Msg::ExportCaption => "All the data we store about you",
+        Msg::DeleteConfirm => "Delete your profile, likes, views and photos? This can't be undone",
+        Msg::DataDeleted => "All your data has been deleted. Press /start to come back",
+        Msg::DeleteCancelled => "Deletion cancelled",
+        Msg::AskReportReason => "What's wrong with this profile?",
+        Msg::ReportThanks => "Thanks, we'll review this profile",
+        Msg::ReasonSpam => "Spam",
+        Msg::ReasonFake => "Fake",
+        Msg::ReasonUnderage => "Underage",
+        Msg::ReasonOffensive => "Offensive content",
+        Msg::ModQueueEmpty => "The moderation queue is empty",
+        Msg::ModReports => "Reports: {count} ({reasons})",
+        Msg::AdminStats => {
+            "Statistics\n\
+            Active today: {active}\n\
+            New profiles: {profiles_today} today, {profiles_week} this week\n\
+            Likes: {likes_today} today, {likes_week} this week\n\
+            Matches this week: {matches_week} ({match_rate}% of likes)\n\
+            Gender: {genders}\n\
+            Onboarding: {onboarding}"
+        }
+        Msg::AdminUserUsage => "Usage: /user <telegram id, @username or profile id>",
+        Msg::AdminUserNotFound => "Profile not found",
+        Msg::AdminUserInfo => {
+            "Status: {status}\n\
+            Created: {created_at}\n\
+            Likes: {likes_given} given, {likes_received} received\n\
+            Matches: {matches}\n\
+            Reports: {reports}"
+        }
+        Msg::AdminBroadcastUsage => "Usage: /broadcast <text>",
+        Msg::AdminBroadcastQueued => "Broadcast to {count} users queued",
+        Msg::ModApproved => "✅ Reports dismissed",
+        Msg::ModShadowBanned => "👻 Profile hidden from the feed",
+        Msg::ModBanned => "⛔ Profile banned",
+        Msg::AskLanguage => "Choose a language",
+        Msg::LanguageChanged => "I speak English now",
+        Msg::NeedStart => "Create your profile first: press /start",
+        Msg::MatchesEmpty => "No matches yet",
+        Msg::MatchesHeader => "Your matches:",
+        Msg::Settings => {
+            "Settings\nLanguage: {language}\nProfile: {visibility}\nContact for matches: {contact}\nTimezone: {timezone}\nReminders: {nudges}"
+        }
+        Msg::SettingsVisible => "visible to others",
+        Msg::SettingsHidden => "hidden",
+        Msg::ContactUsername => "@username",
+        Msg::ContactDeepLink => "Account link",
+        Msg::ContactRelay => "Through the bot only",
+        Msg::ContactRelayHint => "chat through the bot",
+        Msg::ContactChanged => "Privacy settings saved",
+        Msg::ChatOpened => {
+            "Chat with {name}. I'll forward your texts, photos and voice notes, your contacts stay hidden"
+        }
+        Msg::ChatUnavailable => "This chat is no longer available",
+        Msg::RelayUnsupported => "Only texts, photos and voice notes can be forwarded",
+        Msg::YourMatch => "Your match",
+        Msg::Unmatched => "Match removed",
+        Msg::UnmatchedByOther => "One of your matches has unmatched you",
+        Msg::UnmatchConfirm => "Unmatch? The chat will close and you won't see each other again",
+        Msg::SettingsNudgesOn => "on",
+        Msg::SettingsNudgesOff => "don't disturb",
+        Msg::NudgesOn => "Reminders are on",
+        Msg::NudgesOff => "I won't send you reminders anymore",
+        Msg::AskTimezone => "Pick your timezone, I won't send reminders at night",
+        Msg::TimezoneChanged => "Timezone saved",
+        Msg::NudgeIncomplete => "Your profile isn't finished yet. Press /edit, it takes a minute",
+        Msg::NudgeLike => "Someone liked your profile! Take a look in /browse",
+        Msg::NudgeMatch => "{name} is waiting for your message",
+        Msg::LikesHeader => "{count} people liked you. Like back or skip",
+        Msg::LikesEmpty => "No new likes yet",
+        Msg::AskSuperlikeNote => {
+            "A superlike arrives right away. Add a message up to {limit} characters or a voice note"
+        }
+        Msg::SuperlikeLimit => "You're out of superlikes for today, new ones come tomorrow",
+        Msg::SuperlikeCancelled => "Superlike cancelled",
+        Msg::SuperlikeSent => "Superlike sent ⭐",
+        Msg::SuperlikeAlreadySent => "You already sent this profile a superlike",
+        Msg::SuperlikeReceived => "⭐ You got a superlike!",
+        Msg::UndoDone => "Undone, here's that profile again",
+        Msg::UndoUnavailable => "Nothing to undo anymore",
+        Msg::CmdStart => "Start over",
+        Msg::CmdProfile => "My profile and menu",
+        Msg::CmdBrowse => "Browse profiles",
+        Msg::CmdEdit => "Fill in the profile again",
+        Msg::CmdMatches => "Matches",
+        Msg::CmdLikes => "Who liked me",
+        Msg::CmdSettings => "Settings",
+        Msg::CmdPause => "Hide or show my profile",
+        Msg::CmdBlocked => "Blocked people",
+        Msg::CmdLanguage => "Change language",
+        Msg::CmdExport => "Export my data",
+        Msg::CmdDelete => "Delete my profile and data",
+        Msg::CmdCancel => "Cancel and go back to the menu",
+        Msg::BtnGenderFemale => "I'm a girl",
+        Msg::BtnGenderMale => "I'm a guy",
+        Msg::BtnInterestFemale => "Girls",
+        Msg::BtnInterestMale => "Guys",
+        Msg::BtnInterestAny => "Doesn't matter",
+        Msg::BtnLike => "❤️",
+        Msg::BtnSuperlike => "⭐",
+        Msg::BtnUndo => "↩️",
+        Msg::BtnNoNote => "Send without a message",
+        Msg::BtnSkip => "👎",
+        Msg::BtnReport => "🚩",
+        Msg::BtnBlock => "🚫",
+        Msg::BtnMenu => "💤",
+        Msg::BtnBlockMatch => "🚫 Block",
+        Msg::BtnUnblock => "Unblock",
+        Msg::BtnDeleteConfirm => "Yes, delete everything",
+        Msg::BtnCancel => "Cancel",
+        Msg::BtnTogglePause => "Hide / show profile",
+        Msg::BtnChat => "Message through the bot",
+        Msg::BtnChatWith => "💬 {name}",
+        Msg::BtnReply => "Reply",
+        Msg::BtnLeaveChat => "Leave chat",
+        Msg::BtnUnmatch => "Unmatch",
+        Msg::BtnUnmatchNotify => "Unmatch and tell them",
+        Msg::BtnUnmatchSilent => "Unmatch silently",
+        Msg::BtnToggleNudges => "Reminders on/off",
+        Msg::BtnTimezone => "Timezone",
+        Msg::BtnModApprove => "✅ Approve",
+        Msg::BtnModShadowBan => "👻 Shadow ban",
+        Msg::BtnModBan => "⛔ Ban",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The reply keyboards the bot shows, each resolved on its own.
+    const KEYBOARDS: [&[Msg]; 5] = [
+        &[Msg::BtnGenderFemale, Msg::BtnGenderMale],
+        &[
+            Msg::BtnInterestFemale,
+            Msg::BtnInterestMale,
+            Msg::BtnInterestAny,
+        ],
+        &[
+            Msg::BtnLike,
+            Msg::BtnSuperlike,
+            Msg::BtnSkip,
+            Msg::BtnUndo,
+            Msg::BtnReport,
+            Msg::BtnBlock,
+            Msg::BtnMenu,
+        ],
+        &[Msg::BtnNoNote, Msg::BtnCancel],
+        &[Msg::BtnLeaveChat, Msg::BtnUnmatch],
+    ];
+
+    #[test]
+    fn picks_russian_for_neighbouring_languages() {
+        for code in ["ru", "uk", "be", "kk", "ru-RU", "uk_UA"] {
+            assert_eq!(Lang::from_code(Some(code), Lang::En), Lang::Ru, "{code}");
+        }
+    }
+
+    #[test]
+    fn picks_english_for_other_languages() {
+        for code in ["en", "en-GB", "de", "pt-br"] {
+            assert_eq!(Lang::from_code(Some(code), Lang::Ru), Lang::En, "{code}");
+        }
+    }
+
+    #[test]
+    fn falls_back_without_a_language_code() {
+        assert_eq!(Lang::from_code(None, Lang::Ru), Lang::Ru);
+        assert_eq!(Lang::from_code(None, Lang::En), Lang::En);
+    }
+
+    #[test]
+    fn resolves_every_button_label_back_to_its_key() {
+        for buttons in KEYBOARDS {
+            for lang in Lang::ALL {
+                for button in buttons {
+                    let label = lang.t(*button);
+                    assert_eq!(Msg::from_button(label, buttons), Some(*button), "{label}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_text_that_is_no_button() {
+        let buttons = KEYBOARDS[2];
+        assert_eq!(Msg::from_button("hello", buttons), None);
+        assert_eq!(Msg::from_button(Lang::En.t(Msg::BtnCancel), buttons), None);
+    }
+
+    #[test]
+    fn fills_every_placeholder() {
+        let text = Lang::En.f(Msg::HintAge, &[("min", &18), ("max", &99)]);
+        assert_eq!(text, "Please enter your age as a number between 18 and 99");
+        let text = Lang::Ru.f(Msg::DistanceKm, &[("km", &12)]);
+        assert_eq!(text, "12 км от тебя");
+    }
+
+    #[test]
+    fn leaves_unknown_placeholders_alone() {
+        let text = Lang::En.f(Msg::DistanceKm, &[("miles", &7)]);
+        assert_eq!(text, "{km} km away");
+    }
+}
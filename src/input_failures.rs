@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use teloxide::types::ChatId;
+
+/// How many invalid inputs in a row before `/start` is offered as a way out.
+pub const ESCAPE_HATCH_AFTER: u8 = 3;
+
+/// Counts invalid inputs per chat until the next valid one.
+#[derive(Default)]
+pub struct InputFailures {
+    counts: Mutex<HashMap<ChatId, u8>>,
+}
+
+impl InputFailures {
+    /// Records a failure and returns how many happened in a row.
+    pub fn fail(&self, chat_id: ChatId) -> u8 {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(chat_id).or_default();
+        *count = count.saturating_add(1);
+        *count
+    }
+
+    pub fn clear(&self, chat_id: ChatId) {
+        self.counts.lock().unwrap().remove(&chat_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_failures_in_a_row_per_chat() {
+        let failures = InputFailures::default();
+        assert_eq!(failures.fail(ChatId(1)), 1);
+        assert_eq!(failures.fail(ChatId(1)), 2);
+        assert_eq!(failures.fail(ChatId(2)), 1);
+        assert_eq!(failures.fail(ChatId(1)), ESCAPE_HATCH_AFTER);
+    }
+
+    #[test]
+    fn starts_over_after_a_valid_input() {
+        let failures = InputFailures::default();
+        failures.fail(ChatId(1));
+        failures.fail(ChatId(1));
+        failures.clear(ChatId(1));
+        assert_eq!(failures.fail(ChatId(1)), 1);
+    }
+
+    #[test]
+    fn stops_counting_at_the_maximum() {
+        let failures = InputFailures::default();
+        for _ in 0..300 {
+            failures.fail(ChatId(1));
+        }
+        assert_eq!(failures.fail(ChatId(1)), u8::MAX);
+    }
+}
@@ -1,19 +1,59 @@
 use crate::bot::SwagaBot;
+use crate::config::Config;
+use std::env;
 
+mod active_day;
 mod bot;
+mod config;
+mod data_export;
+mod i18n;
+mod input_failures;
 mod match_engine;
+mod moderation;
+mod nudge_scheduler;
+mod outbound_job;
+mod outbound_queue;
 mod pg;
 mod profile;
 mod profile_activities;
+mod profile_block;
+mod profile_card;
+mod profile_like;
+mod profile_match;
+mod profile_report;
+mod profile_superlike;
 mod profile_view;
+mod recycle_policy;
+mod relay_message;
 mod schema;
+mod sent_nudge;
+mod stats;
+mod swipe_journal;
+mod webhook;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     pretty_env_logger::init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {}
+        [flag] if flag == "--migrate-only" => {
+            return pg::run_migrations(&Config::load_database_url()?);
+        }
+        _ => anyhow::bail!(
+            "Unknown arguments {:?}, the only option is --migrate-only",
+            args
+        ),
+    }
+    let config = Config::load()?;
+    if config.run_migrations {
+        pg::run_migrations(&config.database_url)?;
+    }
+    pg::check_schema_version(&config.database_url)?;
+    pg::init_pool(&config)?;
     log::info!("Starting bot");
 
-    SwagaBot::dispatcher().await;
+    SwagaBot::dispatcher(config).await?;
 
     Ok(())
 }
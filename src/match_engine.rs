@@ -1,24 +1,89 @@
 use crate::profile::Profile;
-use crate::profile_activities::ProfileActivity;
 use crate::profile_view::ProfileView;
-use crate::schema::profile_likes::profile_id;
+use crate::recycle_policy::RecyclePolicy;
 use uuid::Uuid;
 
-pub struct MatchEngine();
+/// How many candidates are pulled from the database per round trip.
+const CANDIDATE_BATCH: i64 = 50;
+/// Every third card goes to a liked-you profile unless configured otherwise.
+pub const DEFAULT_LIKED_YOU_EVERY: i32 = 3;
+
+pub enum MatchResult {
+    /// A profile the viewer has never seen.
+    Fresh(Profile),
+    /// Everyone was seen already, this one was skipped earlier and came back.
+    Recycled(Profile),
+    /// Nothing left to show, not even after recycling.
+    Exhausted,
+}
+
+pub struct MatchEngine {
+    policy: RecyclePolicy,
+    hide_threshold: i64,
+    /// Every n-th card goes to someone who already liked the viewer, 0 turns
+    /// this off
+    liked_you_every: i32,
+}
 
 impl MatchEngine {
-    pub fn match_profiles(viewer_id: &Uuid) -> anyhow::Result<Profile> {
-        // Find profile, sort by activity
-        // Check if it is in profile_views
-        // Match by age
-        let profile_activity = ProfileActivity::get_most_active_profile()?;
-        let matched = Profile::get_by_id(profile_activity.viewer_id());
-        let is_viewed = ProfileView::new(*viewer_id, *matched.id()).exists()?;
-        if is_viewed {
-            //todo try to find new profile
-            Ok(matched)
-        } else {
-            Ok(matched)
+    pub fn new(policy: RecyclePolicy, hide_threshold: i64, liked_you_every: i32) -> Self {
+        Self {
+            policy,
+            hide_threshold,
+            liked_you_every,
+        }
+    }
+
+    /// Picks the next card. `turn` is the viewer's running card count and
+    /// decides when a liked-you profile jumps the queue.
+    pub fn match_profiles(&self, viewer_id: &Uuid, turn: i32) -> anyhow::Result<MatchResult> {
+        if self.liked_you_every > 0 && turn % self.liked_you_every == 0 {
+            let liked_you = self.liked_you(viewer_id)?;
+            let ids: Vec<Uuid> = liked_you.iter().map(|profile| *profile.id()).collect();
+            let viewed = ProfileView::viewed_set(viewer_id, &ids)?;
+            if let Some(profile) = liked_you
+                .into_iter()
+                .find(|profile| !viewed.contains(profile.id()))
+            {
+                return Ok(MatchResult::Fresh(profile));
+            }
+        }
+        // Candidates come sorted by activity, skip the ones already viewed
+        if let Some(profile) = self.next_unviewed(viewer_id)? {
+            return Ok(MatchResult::Fresh(profile));
+        }
+        if self.policy.recycle(viewer_id)? == 0 {
+            return Ok(MatchResult::Exhausted);
+        }
+        Ok(match self.next_unviewed(viewer_id)? {
+            Some(profile) => MatchResult::Recycled(profile),
+            None => MatchResult::Exhausted,
+        })
+    }
+
+    /// Profiles that liked the viewer and are still waiting for an answer,
+    /// limited to ones the feed would show.
+    pub fn liked_you(&self, viewer_id: &Uuid) -> anyhow::Result<Vec<Profile>> {
+        Profile::get_pending_likers(viewer_id, self.hide_threshold)
+    }
+
+    fn next_unviewed(&self, viewer_id: &Uuid) -> anyhow::Result<Option<Profile>> {
+        let mut offset = 0;
+        loop {
+            let candidates =
+                Profile::get_candidates(viewer_id, self.hide_threshold, CANDIDATE_BATCH, offset)?;
+            if candidates.is_empty() {
+                return Ok(None);
+            }
+            let ids: Vec<Uuid> = candidates.iter().map(|profile| *profile.id()).collect();
+            let viewed = ProfileView::viewed_set(viewer_id, &ids)?;
+            if let Some(profile) = candidates
+                .into_iter()
+                .find(|profile| !viewed.contains(profile.id()))
+            {
+                return Ok(Some(profile));
+            }
+            offset += CANDIDATE_BATCH;
         }
     }
 }
@@ -0,0 +1,24 @@
+pub const DEFAULT_HIDE_THRESHOLD: i64 = 3;
+
+/// Who may moderate and when reported profiles disappear from the feed.
+pub struct Moderation {
+    admin_ids: Vec<i64>,
+    hide_threshold: i64,
+}
+
+impl Moderation {
+    pub fn new(admin_ids: Vec<i64>, hide_threshold: i64) -> Self {
+        Self {
+            admin_ids,
+            hide_threshold,
+        }
+    }
+
+    pub fn is_admin(&self, user_id: i64) -> bool {
+        self.admin_ids.contains(&user_id)
+    }
+
+    pub fn hide_threshold(&self) -> i64 {
+        self.hide_threshold
+    }
+}
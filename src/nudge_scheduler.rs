@@ -0,0 +1,265 @@
+use crate::i18n::Msg;
+use crate::outbound_job::Delivery;
+use crate::outbound_queue::OutboundQueue;
+use crate::pg::establish_connection;
+use crate::profile::{Profile, ProfileStatus};
+use crate::profile_card::short_label;
+use crate::profile_match::MatchStatus;
+use crate::schema::{
+    profile_blocks, profile_likes, profile_matches, profile_views, profiles, relay_messages,
+    sent_nudges,
+};
+use crate::sent_nudge::{NudgeKind, SentNudge};
+use diesel::dsl::{IntervalDsl, not, now};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel::sql_types::{Text, Timestamp, Timestamptz};
+use std::collections::{HashMap, HashSet};
+use teloxide::prelude::*;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, ReplyMarkup};
+use teloxide::utils::html;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Local hours during which nobody is nudged, `[from, until)` across midnight.
+const QUIET_HOURS: (u32, u32) = (22, 9);
+/// How many reminders of each kind are picked up per round.
+const BATCH: i64 = 100;
+
+/// Text, buttons and the profile the nudge is about.
+type NudgeMessage = (String, Option<ReplyMarkup>, Option<Uuid>);
+/// A reminder worth sending as `(recipient, kind, subject, other profile)`.
+type DueNudge = (Profile, NudgeKind, Uuid, Option<Uuid>);
+
+define_sql_function!(fn timezone(zone: Text, at: Timestamptz) -> Timestamp);
+define_sql_function!(fn date_part(field: Text, at: Timestamp) -> Double);
+
+/// Periodically queues reminders about unfinished onboarding, silent matches
+/// and likes users haven't seen. Every user gets at most one reminder per
+/// cooldown.
+pub struct NudgeScheduler {
+    interval: std::time::Duration,
+    cooldown_hours: i64,
+    /// How long a profile or match may stay untouched before it's nudged about
+    grace_hours: i64,
+    /// Likes from profiles with this many standing reports aren't nudged about
+    hide_threshold: i64,
+}
+
+impl NudgeScheduler {
+    pub fn new(hide_threshold: i64) -> Self {
+        Self {
+            interval: std::time::Duration::from_secs(15 * 60),
+            cooldown_hours: 24,
+            grace_hours: 12,
+            hide_threshold,
+        }
+    }
+
+    /// Runs until `stop` flips, meant to be spawned next to the dispatcher.
+    pub async fn run(self, mut stop: watch::Receiver<bool>) {
+        let mut ticker = tokio::time::interval(self.interval);
+        while !*stop.borrow() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = stop.changed() => break,
+            }
+            if let Err(err) = self.tick() {
+                log::error!("Nudge round failed: {}", err);
+            }
+        }
+    }
+
+    fn tick(&self) -> anyhow::Result<()> {
+        let due = self.due()?;
+        let others: Vec<Uuid> = due.iter().filter_map(|(_, _, _, other)| *other).collect();
+        let others: HashMap<Uuid, Profile> = Profile::get_by_ids(&others)?
+            .into_iter()
+            .map(|profile| (*profile.id(), profile))
+            .collect();
+        let mut nudged = HashSet::new();
+        for (profile, kind, subject_id, other) in due {
+            if !nudged.insert(*profile.id()) {
+                continue;
+            }
+            let other = other.and_then(|other| others.get(&other));
+            let Some((text, markup, about)) = Self::message(&profile, kind, &subject_id, other)
+            else {
+                continue;
+            };
+            let delivery = Delivery {
+                subject_id: about,
+                ..Delivery::default()
+            };
+            OutboundQueue::text(ChatId(*profile.user_id()), text, markup, delivery)?;
+            SentNudge::new(*profile.id(), kind, subject_id).insert()?;
+        }
+        Ok(())
+    }
+
+    /// Ids of the profiles that may get a reminder now: active ones that
+    /// didn't turn reminders off, outside their quiet hours and past the
+    /// cooldown since the last one.
+    fn may_disturb(&self) -> profiles::BoxedQuery<'static, Pg, diesel::sql_types::Uuid> {
+        let recently_nudged = sent_nudges::table
+            .filter(sent_nudges::sent_at.gt(now - self.cooldown_hours.hours()))
+            .select(sent_nudges::profile_id);
+        let local_hour = date_part(
+            "hour",
+            timezone(profiles::timezone, now.into_sql::<Timestamptz>()),
+        );
+        let (from, until) = QUIET_HOURS;
+        profiles::table
+            .filter(profiles::status.eq(ProfileStatus::Active.to_string()))
+            .filter(profiles::do_not_disturb.eq(false))
+            .filter(profiles::bot_blocked.eq(false))
+            .filter(not(profiles::id.eq_any(recently_nudged)))
+            .filter(local_hour.ge(f64::from(until)))
+            .filter(local_hour.lt(f64::from(from)))
+            .select(profiles::id)
+            .into_boxed()
+    }
+
+    /// Everything worth a reminder, most important first, at most `BATCH` of
+    /// each kind.
+    fn due(&self) -> anyhow::Result<Vec<DueNudge>> {
+        let before = now - self.grace_hours.hours();
+        let connection = &mut establish_connection();
+        let mut due = Vec::new();
+
+        let silent: Vec<(Profile, Uuid, Uuid, Uuid)> = profile_matches::table
+            .inner_join(
+                profiles::table.on(profiles::id
+                    .eq(profile_matches::profile_a)
+                    .or(profiles::id.eq(profile_matches::profile_b))),
+            )
+            .left_join(
+                relay_messages::table.on(relay_messages::match_id
+                    .eq(profile_matches::id)
+                    .and(relay_messages::sender_id.eq(profiles::id))),
+            )
+            .left_join(
+                sent_nudges::table.on(sent_nudges::profile_id
+                    .eq(profiles::id)
+                    .and(sent_nudges::kind.eq(NudgeKind::UnansweredMatch.to_string()))
+                    .and(sent_nudges::subject_id.eq(profile_matches::id))),
+            )
+            .filter(profile_matches::status.eq(MatchStatus::Active.to_string()))
+            .filter(profile_matches::created_at.lt(before))
+            .filter(relay_messages::id.is_null())
+            .filter(sent_nudges::profile_id.is_null())
+            .filter(profiles::id.eq_any(self.may_disturb()))
+            .order(profile_matches::created_at)
+            .limit(BATCH)
+            .select((
+                Profile::as_select(),
+                profile_matches::id,
+                profile_matches::profile_a,
+                profile_matches::profile_b,
+            ))
+            .load(connection)?;
+        for (profile, match_id, profile_a, profile_b) in silent {
+            let other = if *profile.id() == profile_a {
+                profile_b
+            } else {
+                profile_a
+            };
+            due.push((profile, NudgeKind::UnansweredMatch, match_id, Some(other)));
+        }
+
+        // Likers are filtered the way the likes inbox filters them
+        let unseen: Vec<(Profile, Uuid)> = profile_likes::table
+            .inner_join(profiles::table.on(profiles::id.eq(profile_likes::profile_id)))
+            .left_join(
+                profile_views::table.on(profile_views::viewer_id
+                    .eq(profile_likes::profile_id)
+                    .and(profile_views::profile_id.eq(profile_likes::viewer_id))),
+            )
+            .left_join(
+                profile_blocks::table.on(profile_blocks::blocker_id
+                    .eq(profile_likes::profile_id)
+                    .and(profile_blocks::blocked_id.eq(profile_likes::viewer_id))
+                    .or(profile_blocks::blocker_id
+                        .eq(profile_likes::viewer_id)
+                        .and(profile_blocks::blocked_id.eq(profile_likes::profile_id)))),
+            )
+            .left_join(
+                sent_nudges::table.on(sent_nudges::profile_id
+                    .eq(profile_likes::profile_id)
+                    .and(sent_nudges::kind.eq(NudgeKind::UnseenLike.to_string()))
+                    .and(sent_nudges::subject_id.eq(profile_likes::viewer_id))),
+            )
+            .filter(profile_views::viewer_id.is_null())
+            .filter(profile_blocks::blocker_id.is_null())
+            .filter(sent_nudges::profile_id.is_null())
+            .filter(profile_likes::viewer_id.eq_any(Profile::shown_to_others(self.hide_threshold)))
+            .filter(profiles::id.eq_any(self.may_disturb()))
+            .order(profile_likes::created_at)
+            .limit(BATCH)
+            .select((Profile::as_select(), profile_likes::viewer_id))
+            .load(connection)?;
+        for (profile, liker) in unseen {
+            due.push((profile, NudgeKind::UnseenLike, liker, None));
+        }
+
+        let incomplete: Vec<Profile> = profiles::table
+            .left_join(
+                sent_nudges::table.on(sent_nudges::profile_id
+                    .eq(profiles::id)
+                    .and(sent_nudges::kind.eq(NudgeKind::IncompleteProfile.to_string()))
+                    .and(sent_nudges::subject_id.eq(profiles::id))),
+            )
+            .filter(profiles::created_at.lt(before))
+            .filter(
+                profiles::age
+                    .eq(0)
+                    .or(profiles::gender.eq(""))
+                    .or(profiles::interests.eq("")),
+            )
+            .filter(sent_nudges::profile_id.is_null())
+            .filter(profiles::id.eq_any(self.may_disturb()))
+            .order(profiles::created_at)
+            .limit(BATCH)
+            .select(Profile::as_select())
+            .load(connection)?;
+        for profile in incomplete {
+            let profile_id = *profile.id();
+            due.push((profile, NudgeKind::IncompleteProfile, profile_id, None));
+        }
+        Ok(due)
+    }
+
+    /// HTML text of the nudge with its buttons, and the other profile it is
+    /// about if any.
+    fn message(
+        profile: &Profile,
+        kind: NudgeKind,
+        subject_id: &Uuid,
+        other: Option<&Profile>,
+    ) -> Option<NudgeMessage> {
+        let lang = profile.language().unwrap_or_default();
+        let message = match kind {
+            NudgeKind::IncompleteProfile => {
+                (html::escape(lang.t(Msg::NudgeIncomplete)), None, None)
+            }
+            NudgeKind::UnseenLike => (
+                html::escape(lang.t(Msg::NudgeLike)),
+                None,
+                Some(*subject_id),
+            ),
+            NudgeKind::UnansweredMatch => {
+                let other = other?;
+                let button = InlineKeyboardButton::callback(
+                    lang.t(Msg::BtnChat),
+                    format!("chat:{subject_id}:open"),
+                );
+                (
+                    html::escape(&lang.f(Msg::NudgeMatch, &[("name", &short_label(other))])),
+                    Some(InlineKeyboardMarkup::new(vec![vec![button]]).into()),
+                    Some(*other.id()),
+                )
+            }
+        };
+        Some(message)
+    }
+}
@@ -0,0 +1,215 @@
+use crate::pg::establish_connection;
+use diesel::dsl::{IntervalDsl, now};
+use diesel::result::Error as DieselError;
+use diesel::{
+    ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
+};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use teloxide::types::ReplyMarkup;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutboundKind {
+    /// HTML text message
+    Text,
+    /// Photo with an optional HTML caption
+    Photo,
+    /// Voice message with an optional HTML caption
+    Voice,
+}
+impl fmt::Display for OutboundKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutboundKind::Text => write!(f, "TEXT"),
+            OutboundKind::Photo => write!(f, "PHOTO"),
+            OutboundKind::Voice => write!(f, "VOICE"),
+        }
+    }
+}
+impl FromStr for OutboundKind {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s {
+            "TEXT" => Self::Text,
+            "PHOTO" => Self::Photo,
+            "VOICE" => Self::Voice,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(kind)
+    }
+}
+
+/// When a queued message may go out and what it belongs to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Delivery {
+    /// Held back for that long, sent right away when `None`
+    pub delay: Option<Duration>,
+    /// Match the message announces, dropped together with an undone match
+    pub match_id: Option<Uuid>,
+    /// Profile the message shows to the recipient, dropped when it's deleted
+    pub subject_id: Option<Uuid>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::outbound_jobs)]
+struct NewOutboundJob {
+    chat_id: i64,
+    kind: String,
+    body: Option<String>,
+    file_id: Option<String>,
+    reply_markup: Option<String>,
+    match_id: Option<Uuid>,
+    subject_id: Option<Uuid>,
+}
+
+/// A message waiting in Postgres to be sent by the outbound queue, so that
+/// notifications survive a restart.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::outbound_jobs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboundJob {
+    id: i64,
+    chat_id: i64,
+    kind: String,
+    body: Option<String>,
+    file_id: Option<String>,
+    reply_markup: Option<String>,
+    attempts: i32,
+}
+
+impl OutboundJob {
+    pub fn chat_id(&self) -> i64 {
+        self.chat_id
+    }
+
+    pub fn kind(&self) -> OutboundKind {
+        self.kind.parse().unwrap_or(OutboundKind::Text)
+    }
+
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+
+    pub fn file_id(&self) -> Option<&str> {
+        self.file_id.as_deref()
+    }
+
+    pub fn reply_markup(&self) -> Option<ReplyMarkup> {
+        let markup = self.reply_markup.as_deref()?;
+        serde_json::from_str(markup).ok()
+    }
+
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
+    /// Queues a message for the chat.
+    pub fn enqueue(
+        chat_id: i64,
+        kind: OutboundKind,
+        body: Option<String>,
+        file_id: Option<String>,
+        reply_markup: Option<ReplyMarkup>,
+        delivery: Delivery,
+    ) -> anyhow::Result<()> {
+        use crate::schema::outbound_jobs;
+        let job = NewOutboundJob {
+            chat_id,
+            kind: kind.to_string(),
+            body,
+            file_id,
+            reply_markup: reply_markup
+                .map(|markup| serde_json::to_string(&markup))
+                .transpose()?,
+            match_id: delivery.match_id,
+            subject_id: delivery.subject_id,
+        };
+        let connection = &mut establish_connection();
+        // Times come from the database clock, like the column default
+        match delivery.delay {
+            Some(delay) => diesel::insert_into(outbound_jobs::table)
+                .values((&job, outbound_jobs::run_after.eq(now + seconds(delay))))
+                .execute(connection)?,
+            None => diesel::insert_into(outbound_jobs::table)
+                .values(&job)
+                .execute(connection)?,
+        };
+        Ok(())
+    }
+
+    /// Queues the same HTML text for many chats at once.
+    pub fn enqueue_text_for_all(chat_ids: &[i64], body: &str) -> anyhow::Result<()> {
+        use crate::schema::outbound_jobs;
+        let jobs: Vec<NewOutboundJob> = chat_ids
+            .iter()
+            .map(|chat_id| NewOutboundJob {
+                chat_id: *chat_id,
+                kind: OutboundKind::Text.to_string(),
+                body: Some(body.to_string()),
+                file_id: None,
+                reply_markup: None,
+                match_id: None,
+                subject_id: None,
+            })
+            .collect();
+        let connection = &mut establish_connection();
+        // Postgres caps a statement at 65535 bind parameters
+        for chunk in jobs.chunks(5000) {
+            diesel::insert_into(outbound_jobs::table)
+                .values(chunk)
+                .execute(connection)?;
+        }
+        Ok(())
+    }
+
+    /// Jobs that are due, oldest first.
+    pub fn due(limit: i64) -> anyhow::Result<Vec<OutboundJob>> {
+        use crate::schema::outbound_jobs::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(outbound_jobs
+            .filter(run_after.le(now))
+            .order((run_after, id))
+            .limit(limit)
+            .select(OutboundJob::as_select())
+            .load(connection)?)
+    }
+
+    /// Puts the job back for another attempt after `delay`.
+    pub fn reschedule(&self, delay: Duration, failed: bool) -> anyhow::Result<()> {
+        use crate::schema::outbound_jobs::dsl::*;
+        let connection = &mut establish_connection();
+        let new_attempts = self.attempts + i32::from(failed);
+        diesel::update(outbound_jobs.find(self.id))
+            .set((
+                run_after.eq(now + seconds(delay)),
+                attempts.eq(new_attempts),
+            ))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Drops the messages still waiting to announce the match.
+    pub fn delete_for_match(announced: &Uuid) -> anyhow::Result<()> {
+        use crate::schema::outbound_jobs::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::delete(outbound_jobs.filter(match_id.eq(announced))).execute(connection)?;
+        Ok(())
+    }
+
+    pub fn delete(&self) -> anyhow::Result<()> {
+        use crate::schema::outbound_jobs::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::delete(outbound_jobs.find(self.id)).execute(connection)?;
+        Ok(())
+    }
+}
+
+/// Whole seconds of the delay as an SQL interval, rounded up so that a job is
+/// never due early.
+fn seconds(delay: Duration) -> diesel::data_types::PgInterval {
+    let secs = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+    i64::try_from(secs).unwrap_or(i64::MAX).seconds()
+}
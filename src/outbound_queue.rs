@@ -0,0 +1,383 @@
+use crate::outbound_job::{Delivery, OutboundJob, OutboundKind};
+use crate::profile::Profile;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use teloxide::prelude::*;
+use teloxide::types::{InputFile, ParseMode, ReplyMarkup};
+use teloxide::{ApiError, RequestError};
+use tokio::sync::watch;
+
+/// Telegram accepts about 30 messages a second across all chats.
+const GLOBAL_PER_SECOND: f64 = 30.0;
+/// ... and about one message a second in a single chat.
+const CHAT_PER_SECOND: f64 = 1.0;
+/// How many due jobs are picked up per round.
+const BATCH: i64 = 100;
+/// A job is dropped after failing this many times.
+const MAX_ATTEMPTS: i32 = 5;
+/// How long pending messages are still sent after shutdown was requested.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether sending failed because the chat can't be written to anymore, the
+/// user blocked the bot or deleted their account.
+fn is_unreachable(err: &RequestError) -> bool {
+    matches!(
+        err,
+        RequestError::Api(
+            ApiError::BotBlocked | ApiError::UserDeactivated | ApiError::CantInitiateConversation
+        )
+    )
+}
+
+/// What to do with a job after trying to send it.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Sent,
+    /// Send again after the delay, `failed` counts it as an attempt
+    Retry {
+        after: Duration,
+        failed: bool,
+    },
+    /// The chat can't be written to anymore
+    Unreachable,
+    /// Failed too often, give up
+    Drop,
+}
+
+/// Decides about a job that has failed `attempts` times before. Telegram's
+/// `RetryAfter` doesn't count as a failure, other errors back off
+/// exponentially until `MAX_ATTEMPTS`.
+fn outcome(result: &Result<(), RequestError>, attempts: i32) -> Outcome {
+    match result {
+        Ok(()) => Outcome::Sent,
+        Err(RequestError::RetryAfter(wait)) => Outcome::Retry {
+            after: wait.duration(),
+            failed: false,
+        },
+        Err(err) if is_unreachable(err) => Outcome::Unreachable,
+        Err(_) if attempts + 1 >= MAX_ATTEMPTS => Outcome::Drop,
+        Err(_) => Outcome::Retry {
+            after: Duration::from_secs(30 << attempts),
+            failed: true,
+        },
+    }
+}
+
+/// Classic token bucket: holds up to `capacity` tokens and gains `rate` of
+/// them every second.
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, rate: f64) -> Self {
+        Self::new_at(capacity, rate, Instant::now())
+    }
+
+    fn new_at(capacity: f64, rate: f64, now: Instant) -> Self {
+        Self {
+            capacity,
+            rate,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn try_take(&mut self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    fn try_take_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Waits until a token is available and takes it.
+    async fn take(&mut self) {
+        while !self.try_take() {
+            let missing = (1.0 - self.tokens) / self.rate;
+            tokio::time::sleep(std::time::Duration::from_secs_f64(missing)).await;
+        }
+    }
+
+    fn is_full(&mut self) -> bool {
+        self.refill(Instant::now());
+        self.tokens >= self.capacity
+    }
+}
+
+/// Sends the messages queued in `outbound_jobs`, throttled globally and per
+/// chat. Jobs are retried after `RetryAfter` and other transient failures,
+/// and users who blocked the bot are flagged instead.
+pub struct OutboundQueue {
+    poll: std::time::Duration,
+    global: TokenBucket,
+    chats: HashMap<i64, TokenBucket>,
+}
+
+impl Default for OutboundQueue {
+    fn default() -> Self {
+        Self {
+            poll: std::time::Duration::from_millis(500),
+            global: TokenBucket::new(GLOBAL_PER_SECOND, GLOBAL_PER_SECOND),
+            chats: HashMap::new(),
+        }
+    }
+}
+
+impl OutboundQueue {
+    /// Queues an HTML text message.
+    pub fn text(
+        chat_id: ChatId,
+        html: String,
+        markup: Option<ReplyMarkup>,
+        delivery: Delivery,
+    ) -> anyhow::Result<()> {
+        OutboundJob::enqueue(
+            chat_id.0,
+            OutboundKind::Text,
+            Some(html),
+            None,
+            markup,
+            delivery,
+        )
+    }
+
+    /// Queues a photo with an HTML caption.
+    pub fn photo(
+        chat_id: ChatId,
+        file_id: &str,
+        caption: String,
+        markup: Option<ReplyMarkup>,
+        delivery: Delivery,
+    ) -> anyhow::Result<()> {
+        OutboundJob::enqueue(
+            chat_id.0,
+            OutboundKind::Photo,
+            Some(caption),
+            Some(file_id.to_string()),
+            markup,
+            delivery,
+        )
+    }
+
+    /// Queues a voice message with an optional HTML caption.
+    pub fn voice(
+        chat_id: ChatId,
+        file_id: &str,
+        caption: Option<String>,
+        markup: Option<ReplyMarkup>,
+        delivery: Delivery,
+    ) -> anyhow::Result<()> {
+        OutboundJob::enqueue(
+            chat_id.0,
+            OutboundKind::Voice,
+            caption,
+            Some(file_id.to_string()),
+            markup,
+            delivery,
+        )
+    }
+
+    /// Sends queued messages until `stop` flips, then keeps going until nothing
+    /// due is left or `DRAIN_TIMEOUT` runs out. Meant to be spawned next to the
+    /// dispatcher.
+    pub async fn run(mut self, bot: Bot, mut stop: watch::Receiver<bool>) {
+        let mut ticker = tokio::time::interval(self.poll);
+        while !*stop.borrow() {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = stop.changed() => break,
+            }
+            self.round(&bot).await;
+        }
+        log::info!("Flushing the outbound queue");
+        let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+            while OutboundJob::due(1).is_ok_and(|jobs| !jobs.is_empty()) {
+                ticker.tick().await;
+                self.round(&bot).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            log::warn!("Outbound queue not flushed in time, the rest is sent on next start");
+        }
+    }
+
+    async fn round(&mut self, bot: &Bot) {
+        if let Err(err) = self.tick(bot).await {
+            log::error!("Outbound round failed: {}", err);
+        }
+        self.chats.retain(|_, bucket| !bucket.is_full());
+    }
+
+    async fn tick(&mut self, bot: &Bot) -> anyhow::Result<()> {
+        // Chats that must wait, so their messages keep their order
+        let mut held = HashSet::new();
+        for job in OutboundJob::due(BATCH)? {
+            let chat_id = job.chat_id();
+            if held.contains(&chat_id) {
+                continue;
+            }
+            let bucket = self
+                .chats
+                .entry(chat_id)
+                .or_insert_with(|| TokenBucket::new(1.0, CHAT_PER_SECOND));
+            if !bucket.try_take() {
+                held.insert(chat_id);
+                continue;
+            }
+            self.global.take().await;
+            let result = Self::send(bot, &job).await;
+            match outcome(&result, job.attempts()) {
+                Outcome::Sent => job.delete()?,
+                Outcome::Retry { after, failed } => {
+                    if let Err(err) = result
+                        && failed
+                    {
+                        log::warn!("Message to {} failed, will retry: {}", chat_id, err);
+                    }
+                    held.insert(chat_id);
+                    job.reschedule(after, failed)?;
+                }
+                Outcome::Unreachable => {
+                    log::info!("Chat {} is unreachable, flagging it", chat_id);
+                    Profile::update_bot_blocked(chat_id, true)?;
+                    job.delete()?;
+                }
+                Outcome::Drop => {
+                    if let Err(err) = result {
+                        log::error!("Dropping message to {}: {}", chat_id, err);
+                    }
+                    job.delete()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(bot: &Bot, job: &OutboundJob) -> Result<(), RequestError> {
+        let chat_id = ChatId(job.chat_id());
+        let body = job.body().unwrap_or_default().to_string();
+        let markup = job.reply_markup();
+        match (job.kind(), job.file_id()) {
+            (OutboundKind::Photo, Some(file_id)) => {
+                let mut request = bot
+                    .send_photo(chat_id, InputFile::file_id(file_id.to_string()))
+                    .caption(body)
+                    .parse_mode(ParseMode::Html);
+                if let Some(markup) = markup {
+                    request = request.reply_markup(markup);
+                }
+                request.await?;
+            }
+            (OutboundKind::Voice, Some(file_id)) => {
+                let mut request = bot.send_voice(chat_id, InputFile::file_id(file_id.to_string()));
+                if !body.is_empty() {
+                    request = request.caption(body).parse_mode(ParseMode::Html);
+                }
+                if let Some(markup) = markup {
+                    request = request.reply_markup(markup);
+                }
+                request.await?;
+            }
+            _ => {
+                let mut request = bot.send_message(chat_id, body).parse_mode(ParseMode::Html);
+                if let Some(markup) = markup {
+                    request = request.reply_markup(markup);
+                }
+                request.await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use teloxide::types::Seconds;
+
+    fn transient() -> Result<(), RequestError> {
+        Err(RequestError::Io(Arc::new(std::io::Error::other("reset"))))
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_up_to_its_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new_at(3.0, 1.0, start);
+        assert!((0..3).all(|_| bucket.try_take_at(start)));
+        assert!(!bucket.try_take_at(start));
+    }
+
+    #[test]
+    fn bucket_refills_at_its_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new_at(30.0, 30.0, start);
+        while bucket.try_take_at(start) {}
+        let later = start + Duration::from_millis(100);
+        assert_eq!((0..10).filter(|_| bucket.try_take_at(later)).count(), 3);
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_its_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new_at(2.0, 1.0, start);
+        while bucket.try_take_at(start) {}
+        let much_later = start + Duration::from_secs(60);
+        assert_eq!(
+            (0..10).filter(|_| bucket.try_take_at(much_later)).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn waits_as_long_as_telegram_asks_without_counting_a_failure() {
+        let result = Err(RequestError::RetryAfter(Seconds::from_seconds(7)));
+        let expected = Outcome::Retry {
+            after: Duration::from_secs(7),
+            failed: false,
+        };
+        assert_eq!(outcome(&result, MAX_ATTEMPTS - 1), expected);
+    }
+
+    #[test]
+    fn backs_off_exponentially_on_other_errors() {
+        let delays: Vec<_> = (0..MAX_ATTEMPTS - 1)
+            .map(|attempts| outcome(&transient(), attempts))
+            .collect();
+        let expected: Vec<_> = [30, 60, 120, 240]
+            .map(|secs| Outcome::Retry {
+                after: Duration::from_secs(secs),
+                failed: true,
+            })
+            .into();
+        assert_eq!(delays, expected);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_attempt() {
+        assert_eq!(outcome(&transient(), MAX_ATTEMPTS - 1), Outcome::Drop);
+    }
+
+    #[test]
+    fn flags_chats_that_blocked_the_bot() {
+        let result = Err(RequestError::Api(ApiError::BotBlocked));
+        assert_eq!(outcome(&result, 0), Outcome::Unreachable);
+        assert_eq!(outcome(&Ok(()), 0), Outcome::Sent);
+    }
+}
@@ -1,11 +1,102 @@
+use crate::config::Config;
+use diesel::migration::MigrationSource;
+use diesel::pg::Pg;
 use diesel::prelude::*;
-use dotenvy::dotenv;
-use std::env;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use std::collections::HashSet;
+use std::sync::OnceLock;
 
-pub fn establish_connection() -> PgConnection {
-    dotenv().ok();
+/// Everything in `migrations/`, compiled into the binary.
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
+
+type PgPool = Pool<ConnectionManager<PgConnection>>;
+
+static POOL: OnceLock<PgPool> = OnceLock::new();
+
+/// Opens the connection pool, must run once before any query.
+pub fn init_pool(config: &Config) -> anyhow::Result<()> {
     log::info!("Connection to postgres");
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    PgConnection::establish(&database_url)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
+    POOL.set(build_pool(&config.database_url, config.pool_size)?)
+        .map_err(|_| anyhow::anyhow!("The connection pool is already set up"))
+}
+
+fn build_pool(database_url: &str, pool_size: u32) -> anyhow::Result<PgPool> {
+    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    Pool::builder()
+        .max_size(pool_size)
+        .build(manager)
+        .map_err(|err| anyhow::anyhow!("Error connecting to the database: {err}"))
+}
+
+/// Applies the embedded migrations the database doesn't have yet.
+pub fn run_migrations(database_url: &str) -> anyhow::Result<()> {
+    let connection = &mut PgConnection::establish(database_url)?;
+    let applied = connection
+        .run_pending_migrations(MIGRATIONS)
+        .map_err(|err| anyhow::anyhow!("Migrations failed: {err}"))?;
+    for version in &applied {
+        log::info!("Applied migration {}", version);
+    }
+    log::info!(
+        "Database schema is up to date, {} migrations applied",
+        applied.len()
+    );
+    Ok(())
+}
+
+/// Makes sure the database has exactly the migrations this binary was built
+/// with, so a stale schema is reported at startup instead of failing queries.
+pub fn check_schema_version(database_url: &str) -> anyhow::Result<()> {
+    let connection = &mut PgConnection::establish(database_url)?;
+    let known: HashSet<String> = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|err| anyhow::anyhow!("Embedded migrations are broken: {err}"))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+    let applied: HashSet<String> = connection
+        .applied_migrations()
+        .map_err(|err| anyhow::anyhow!("Couldn't read the applied migrations: {err}"))?
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let mut pending: Vec<&String> = known.difference(&applied).collect();
+    if !pending.is_empty() {
+        pending.sort();
+        anyhow::bail!(
+            "The database schema is behind this binary, pending migrations: {:?}. \
+            Run with --migrate-only or set RUN_MIGRATIONS=true",
+            pending
+        );
+    }
+    let mut unknown: Vec<&String> = applied.difference(&known).collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        anyhow::bail!(
+            "The database schema is ahead of this binary, unknown migrations: {:?}",
+            unknown
+        );
+    }
+    Ok(())
+}
+
+/// Points the pool at `DATABASE_URL` for tests that need a real database and
+/// brings its schema up to date. Such tests are `#[ignore]`d, run them with
+/// `DATABASE_URL=... cargo test -- --ignored`.
+#[cfg(test)]
+pub fn init_test_pool() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        run_migrations(&database_url).expect("migrating the test database");
+        POOL.set(build_pool(&database_url, 16).expect("connecting to the test database"))
+            .ok();
+    });
+}
+
+pub fn establish_connection() -> PooledConnection<ConnectionManager<PgConnection>> {
+    POOL.get()
+        .expect("init_pool must be called at startup")
+        .get()
+        .unwrap_or_else(|err| panic!("Error getting a database connection: {err}"))
 }
@@ -1,20 +1,36 @@
+use crate::i18n::Lang;
 use crate::pg::establish_connection;
+use crate::profile_report::ReportStatus;
+use crate::profile_view::ViewAction;
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use diesel::dsl::{count_star, not};
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
+use std::fmt;
 use std::str::FromStr;
 use uuid::Uuid;
 
 use crate::schema::profiles;
 
+define_sql_function!(fn sha256(data: diesel::sql_types::Bytea) -> diesel::sql_types::Bytea);
+
+/// Key of a deleted profile's tombstone, so that a user coming back is
+/// recognised without keeping their Telegram id.
+fn user_hash(user_id: i64) -> sha256<Vec<u8>> {
+    sha256(user_id.to_be_bytes().to_vec())
+}
+
 pub enum ProfileGender {
     Male,
     Female,
 }
-impl ToString for ProfileGender {
-    fn to_string(&self) -> String {
+impl fmt::Display for ProfileGender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ProfileGender::Male => "MALE".to_string(),
-            ProfileGender::Female => "FEMALE".to_string(),
+            ProfileGender::Male => write!(f, "MALE"),
+            ProfileGender::Female => write!(f, "FEMALE"),
         }
     }
 }
@@ -31,18 +47,118 @@ impl FromStr for ProfileGender {
     }
 }
 
-#[derive(Queryable, Selectable, Insertable)]
+pub enum ProfileInterest {
+    Male,
+    Female,
+    Any,
+}
+impl fmt::Display for ProfileInterest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileInterest::Male => write!(f, "MALE"),
+            ProfileInterest::Female => write!(f, "FEMALE"),
+            ProfileInterest::Any => write!(f, "ANY"),
+        }
+    }
+}
+
+/// Moderation and visibility state of a profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileStatus {
+    /// Browses and is shown to others.
+    Active,
+    /// Hidden from others by the owner, data is kept.
+    Paused,
+    /// Browses as usual but is never shown to anyone.
+    ShadowBanned,
+    /// Cannot use the bot at all.
+    Banned,
+}
+impl fmt::Display for ProfileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileStatus::Active => write!(f, "ACTIVE"),
+            ProfileStatus::Paused => write!(f, "PAUSED"),
+            ProfileStatus::ShadowBanned => write!(f, "SHADOW_BANNED"),
+            ProfileStatus::Banned => write!(f, "BANNED"),
+        }
+    }
+}
+impl FromStr for ProfileStatus {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let status = match s {
+            "ACTIVE" => Self::Active,
+            "PAUSED" => Self::Paused,
+            "SHADOW_BANNED" => Self::ShadowBanned,
+            "BANNED" => Self::Banned,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(status)
+    }
+}
+
+/// How a match gets in touch with the profile owner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContactMode {
+    /// The @username is shown to matches.
+    #[default]
+    Username,
+    /// A link to the Telegram account, works without a public username.
+    DeepLink,
+    /// Nothing is revealed, matches talk through the bot.
+    Relay,
+}
+impl ContactMode {
+    pub const ALL: [ContactMode; 3] = [Self::Username, Self::DeepLink, Self::Relay];
+}
+impl fmt::Display for ContactMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContactMode::Username => write!(f, "USERNAME"),
+            ContactMode::DeepLink => write!(f, "DEEP_LINK"),
+            ContactMode::Relay => write!(f, "RELAY"),
+        }
+    }
+}
+impl FromStr for ContactMode {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mode = match s {
+            "USERNAME" => Self::Username,
+            "DEEP_LINK" => Self::DeepLink,
+            "RELAY" => Self::Relay,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(mode)
+    }
+}
+
+#[derive(Clone, Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::schema::profiles)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Profile {
     id: Uuid,
     user_id: i64,
     username: String,
+    created_at: NaiveDateTime,
     description: String,
+    file_ids: Option<Vec<Option<String>>>,
     displayed_name: String,
     location: String,
     age: i32,
     gender: String,
+    interests: String,
+    status: String,
+    language: Option<String>,
+    contact_mode: String,
+    do_not_disturb: bool,
+    timezone: String,
+    bot_blocked: bool,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
 }
 
 impl Profile {
@@ -55,9 +171,21 @@ impl Profile {
     pub fn username(&self) -> &str {
         &self.username
     }
+    pub fn created_at(&self) -> &NaiveDateTime {
+        &self.created_at
+    }
     pub fn description(&self) -> &str {
         &self.description
     }
+    /// Telegram `file_id`s of the uploaded photos and videos.
+    pub fn file_ids(&self) -> Vec<&str> {
+        self.file_ids
+            .iter()
+            .flatten()
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
     pub fn displayed_name(&self) -> &str {
         &self.displayed_name
     }
@@ -67,27 +195,95 @@ impl Profile {
     pub fn location(&self) -> &str {
         &self.location
     }
-    pub fn new(user_id: i64, username: Option<String>) -> Self {
+    pub fn gender(&self) -> &str {
+        &self.gender
+    }
+    pub fn interests(&self) -> &str {
+        &self.interests
+    }
+    pub fn status(&self) -> ProfileStatus {
+        self.status.parse().unwrap_or(ProfileStatus::Active)
+    }
+    /// Language chosen for the profile, `None` for profiles created before
+    /// languages were stored.
+    pub fn language(&self) -> Option<Lang> {
+        self.language.as_deref().and_then(|code| code.parse().ok())
+    }
+    pub fn contact_mode(&self) -> ContactMode {
+        self.contact_mode.parse().unwrap_or_default()
+    }
+    pub fn do_not_disturb(&self) -> bool {
+        self.do_not_disturb
+    }
+    /// Timezone used for quiet hours, Moscow time when the stored name is unknown.
+    pub fn timezone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::Europe__Moscow)
+    }
+    /// Whether the user blocked the bot, such profiles are kept out of matching.
+    pub fn bot_blocked(&self) -> bool {
+        self.bot_blocked
+    }
+    /// Where the user last shared their location from, as `(latitude,
+    /// longitude)`.
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+    /// Whether the profile's card may be pushed to other users, which shadow
+    /// banned, paused and unreachable profiles may not.
+    pub fn may_reach_others(&self) -> bool {
+        self.status() == ProfileStatus::Active && !self.bot_blocked
+    }
+    pub fn new(user_id: i64, username: Option<String>, language: Lang) -> Self {
         Self {
             id: Uuid::new_v4(),
             user_id,
             username: username.unwrap(),
+            created_at: Utc::now().naive_utc(),
             description: String::new(),
+            file_ids: None,
             displayed_name: String::new(),
             location: String::new(),
             age: 0,
             gender: String::new(),
+            interests: String::new(),
+            status: ProfileStatus::Active.to_string(),
+            language: Some(language.to_string()),
+            contact_mode: ContactMode::default().to_string(),
+            do_not_disturb: false,
+            timezone: Tz::Europe__Moscow.name().to_string(),
+            bot_blocked: false,
+            latitude: None,
+            longitude: None,
         }
     }
 
+    /// Stores the new profile. A user who deleted their profile before gets
+    /// the old id back, so reports against it keep counting, along with a ban.
     pub fn insert(&self) -> anyhow::Result<Profile> {
+        use crate::schema::deleted_profiles;
         let connection = &mut establish_connection();
-
-        let profile = diesel::insert_into(profiles::table)
-            .values(self)
-            .returning(Profile::as_returning())
-            .get_result(connection)?;
-
+        let profile = connection.transaction::<_, DieselError, _>(|connection| {
+            let deleted = diesel::delete(
+                deleted_profiles::table
+                    .filter(deleted_profiles::user_hash.eq(user_hash(self.user_id))),
+            )
+            .returning((deleted_profiles::profile_id, deleted_profiles::status))
+            .get_result::<(Uuid, String)>(connection)
+            .optional()?;
+            let mut profile = self.clone();
+            if let Some((old_id, old_status)) = deleted {
+                profile.id = old_id;
+                if let Ok(status @ (ProfileStatus::ShadowBanned | ProfileStatus::Banned)) =
+                    old_status.parse::<ProfileStatus>()
+                {
+                    profile.status = status.to_string();
+                }
+            }
+            diesel::insert_into(profiles::table)
+                .values(&profile)
+                .returning(Profile::as_returning())
+                .get_result(connection)
+        })?;
         Ok(profile)
     }
 
@@ -111,13 +307,416 @@ impl Profile {
         Ok(())
     }
 
-    pub fn get_by_id(profile_id: &Uuid) -> Profile {
+    pub fn update_interests(msg_username: &str, profile_interests: &str) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles)
+            .filter(username.eq(msg_username))
+            .set(interests.eq(profile_interests))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_description(msg_username: &str, new_description: &str) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles)
+            .filter(username.eq(msg_username))
+            .set(description.eq(new_description))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Replaces the uploaded media with a single photo.
+    pub fn update_photo(msg_username: &str, file_id: &str) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles)
+            .filter(username.eq(msg_username))
+            .set(file_ids.eq(Some(vec![Some(file_id.to_string())])))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_language(profile_id: &Uuid, new_language: Lang) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles.find(profile_id))
+            .set(language.eq(new_language.to_string()))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_contact_mode(profile_id: &Uuid, mode: ContactMode) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles.find(profile_id))
+            .set(contact_mode.eq(mode.to_string()))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_coordinates(
+        msg_username: &str,
+        new_latitude: f64,
+        new_longitude: f64,
+    ) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles)
+            .filter(username.eq(msg_username))
+            .set((latitude.eq(new_latitude), longitude.eq(new_longitude)))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_timezone(profile_id: &Uuid, new_timezone: Tz) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles.find(profile_id))
+            .set(timezone.eq(new_timezone.name()))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Flips the "don't disturb" setting and returns the new value.
+    pub fn toggle_do_not_disturb(profile_id: &Uuid) -> anyhow::Result<bool> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(diesel::update(profiles.find(profile_id))
+            .set(do_not_disturb.eq(not(do_not_disturb)))
+            .returning(do_not_disturb)
+            .get_result(connection)?)
+    }
+
+    /// Records whether the Telegram user has blocked the bot.
+    pub fn update_bot_blocked(msg_user_id: i64, blocked: bool) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles.filter(user_id.eq(msg_user_id)))
+            .set(bot_blocked.eq(blocked))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn update_status(profile_id: &Uuid, new_status: ProfileStatus) -> anyhow::Result<()> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profiles.find(profile_id))
+            .set(status.eq(new_status.to_string()))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Flips an active profile to paused and back. Returns the new status, other
+    /// statuses are left untouched.
+    pub fn toggle_pause(profile_id: &Uuid) -> anyhow::Result<Option<ProfileStatus>> {
+        let Some(profile) = Self::get_by_id(profile_id)? else {
+            return Ok(None);
+        };
+        let new_status = match profile.status() {
+            ProfileStatus::Active => ProfileStatus::Paused,
+            ProfileStatus::Paused => ProfileStatus::Active,
+            _ => return Ok(None),
+        };
+        Self::update_status(profile_id, new_status)?;
+        Ok(Some(new_status))
+    }
+
+    /// Erases the profile, its media references and everything it left in the
+    /// m2m tables in one transaction. Reports against the profile are kept,
+    /// with a tombstone under a hash of the Telegram id to find them again.
+    pub fn delete_with_data(profile_id: &Uuid) -> anyhow::Result<()> {
+        use crate::schema::{
+            active_days, deleted_profiles, outbound_jobs, profile_activities, profile_blocks,
+            profile_likes, profile_matches, profile_reports, profile_superlikes, profile_views,
+            sent_nudges,
+        };
+        let connection = &mut establish_connection();
+        connection.transaction::<_, DieselError, _>(|connection| {
+            let owner = profiles::table
+                .find(profile_id)
+                .select((profiles::user_id, profiles::status))
+                .first::<(i64, String)>(connection)
+                .optional()?;
+            if let Some((user_id, status)) = &owner {
+                diesel::insert_into(deleted_profiles::table)
+                    .values((
+                        deleted_profiles::user_hash.eq(user_hash(*user_id)),
+                        deleted_profiles::profile_id.eq(profile_id),
+                        deleted_profiles::status.eq(status),
+                    ))
+                    .on_conflict(deleted_profiles::user_hash)
+                    .do_update()
+                    .set((
+                        deleted_profiles::profile_id.eq(profile_id),
+                        deleted_profiles::status.eq(status),
+                        deleted_profiles::deleted_at.eq(diesel::dsl::now),
+                    ))
+                    .execute(connection)?;
+            }
+            let user_id = owner.map(|(user_id, _)| user_id);
+            diesel::delete(active_days::table.filter(active_days::user_id.nullable().eq(user_id)))
+                .execute(connection)?;
+            // Messages to the user and the ones showing them to others
+            let announced_matches = profile_matches::table
+                .filter(
+                    profile_matches::profile_a
+                        .eq(profile_id)
+                        .or(profile_matches::profile_b.eq(profile_id)),
+                )
+                .select(profile_matches::id.nullable());
+            diesel::delete(
+                outbound_jobs::table.filter(
+                    outbound_jobs::chat_id
+                        .nullable()
+                        .eq(user_id)
+                        .or(outbound_jobs::subject_id.eq(profile_id))
+                        .or(outbound_jobs::match_id.eq_any(announced_matches)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(
+                profile_views::table.filter(
+                    profile_views::viewer_id
+                        .eq(profile_id)
+                        .or(profile_views::profile_id.eq(profile_id)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(
+                profile_likes::table.filter(
+                    profile_likes::viewer_id
+                        .eq(profile_id)
+                        .or(profile_likes::profile_id.eq(profile_id)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(
+                profile_superlikes::table.filter(
+                    profile_superlikes::viewer_id
+                        .eq(profile_id)
+                        .or(profile_superlikes::profile_id.eq(profile_id)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(profile_activities::table.find(profile_id)).execute(connection)?;
+            diesel::delete(
+                profile_blocks::table.filter(
+                    profile_blocks::blocker_id
+                        .eq(profile_id)
+                        .or(profile_blocks::blocked_id.eq(profile_id)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(
+                profile_reports::table.filter(profile_reports::reporter_id.eq(profile_id)),
+            )
+            .execute(connection)?;
+            // Nudges about the profile, or about one of its matches
+            let match_ids = profile_matches::table
+                .filter(
+                    profile_matches::profile_a
+                        .eq(profile_id)
+                        .or(profile_matches::profile_b.eq(profile_id)),
+                )
+                .select(profile_matches::id);
+            diesel::delete(
+                sent_nudges::table.filter(
+                    sent_nudges::profile_id
+                        .eq(profile_id)
+                        .or(sent_nudges::subject_id.eq(profile_id))
+                        .or(sent_nudges::subject_id.eq_any(match_ids)),
+                ),
+            )
+            .execute(connection)?;
+            // Relayed messages go along with their matches
+            diesel::delete(
+                profile_matches::table.filter(
+                    profile_matches::profile_a
+                        .eq(profile_id)
+                        .or(profile_matches::profile_b.eq(profile_id)),
+                ),
+            )
+            .execute(connection)?;
+            diesel::delete(profiles::table.find(profile_id)).execute(connection)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Status of the Telegram user's profile, banned ones included. This is the
+    /// only lookup that sees banned profiles, use it to turn them away.
+    pub fn get_status_by_user_id(msg_user_id: i64) -> anyhow::Result<Option<ProfileStatus>> {
+        use crate::schema::profiles::dsl::*;
         let connection = &mut establish_connection();
-        profiles::dsl::profiles
+        let profile_status = profiles
+            .filter(user_id.eq(msg_user_id))
+            .select(status)
+            .first::<String>(connection)
+            .optional()?;
+        Ok(profile_status.and_then(|value| value.parse().ok()))
+    }
+
+    pub fn get_by_id(profile_id: &Uuid) -> anyhow::Result<Option<Profile>> {
+        let connection = &mut establish_connection();
+        Ok(profiles::dsl::profiles
             .find(profile_id)
+            .filter(profiles::status.ne(ProfileStatus::Banned.to_string()))
+            .select(Profile::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    /// Loads the given profiles, banned ones left out.
+    pub fn get_by_ids(profile_ids: &[Uuid]) -> anyhow::Result<Vec<Profile>> {
+        if profile_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let connection = &mut establish_connection();
+        Ok(profiles::table
+            .filter(profiles::id.eq_any(profile_ids))
+            .filter(profiles::status.ne(ProfileStatus::Banned.to_string()))
+            .select(Profile::as_select())
+            .load(connection)?)
+    }
+
+    /// Ids of the profiles that may be shown to others: active ones of users
+    /// who didn't block the bot, without profiles with at least
+    /// `hide_threshold` standing reports.
+    pub fn shown_to_others(
+        hide_threshold: i64,
+    ) -> profiles::BoxedQuery<'static, Pg, diesel::sql_types::Uuid> {
+        use crate::schema::profile_reports;
+        let counted: Vec<String> = ReportStatus::COUNTED
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let hidden = profile_reports::table
+            .filter(profile_reports::status.eq_any(counted))
+            .group_by(profile_reports::profile_id)
+            .having(count_star().ge(hide_threshold))
+            .select(profile_reports::profile_id);
+        profiles::table
+            .filter(profiles::status.eq(ProfileStatus::Active.to_string()))
+            .filter(profiles::bot_blocked.eq(false))
+            .filter(not(profiles::id.eq_any(hidden)))
+            .select(profiles::id)
+            .into_boxed()
+    }
+
+    /// Ids of the profiles the viewer may be shown: the ones shown to others,
+    /// without the viewer, profiles the viewer blocked or was blocked by and
+    /// past matches.
+    fn visible_to(
+        viewer_id: &Uuid,
+        hide_threshold: i64,
+    ) -> profiles::BoxedQuery<'static, Pg, diesel::sql_types::Uuid> {
+        use crate::schema::{profile_blocks, profile_matches};
+        let viewer_id = *viewer_id;
+        let blocked = profile_blocks::table
+            .filter(profile_blocks::blocker_id.eq(viewer_id))
+            .select(profile_blocks::blocked_id);
+        let blocked_by = profile_blocks::table
+            .filter(profile_blocks::blocked_id.eq(viewer_id))
+            .select(profile_blocks::blocker_id);
+        // Matched pairs never meet in the feed again, even after the match ended
+        let matched_as_a = profile_matches::table
+            .filter(profile_matches::profile_a.eq(viewer_id))
+            .select(profile_matches::profile_b);
+        let matched_as_b = profile_matches::table
+            .filter(profile_matches::profile_b.eq(viewer_id))
+            .select(profile_matches::profile_a);
+        Self::shown_to_others(hide_threshold)
+            .filter(profiles::id.ne(viewer_id))
+            .filter(not(profiles::id.eq_any(blocked)))
+            .filter(not(profiles::id.eq_any(blocked_by)))
+            .filter(not(profiles::id.eq_any(matched_as_a)))
+            .filter(not(profiles::id.eq_any(matched_as_b)))
+    }
+
+    /// Loads a page of the profiles the viewer may be shown, most active
+    /// first.
+    pub fn get_candidates(
+        viewer_id: &Uuid,
+        hide_threshold: i64,
+        limit: i64,
+        offset: i64,
+    ) -> anyhow::Result<Vec<Profile>> {
+        use crate::schema::profile_activities;
+        let connection = &mut establish_connection();
+        Ok(profiles::table
+            .left_join(profile_activities::table.on(profile_activities::viewer_id.eq(profiles::id)))
+            .filter(profiles::id.eq_any(Self::visible_to(viewer_id, hide_threshold)))
+            .order((
+                profile_activities::activity_count.desc().nulls_last(),
+                profiles::created_at.desc(),
+            ))
+            .limit(limit)
+            .offset(offset)
+            .select(Profile::as_select())
+            .load(connection)?)
+    }
+
+    /// Profiles that liked the viewer and got neither a like nor a skip back,
+    /// limited to the ones the viewer may be shown, oldest like first.
+    pub fn get_pending_likers(
+        viewer_id: &Uuid,
+        hide_threshold: i64,
+    ) -> anyhow::Result<Vec<Profile>> {
+        use crate::schema::{profile_likes, profile_views};
+        let connection = &mut establish_connection();
+        Ok(profiles::table
+            .inner_join(profile_likes::table.on(profile_likes::viewer_id.eq(profiles::id)))
+            .left_join(
+                profile_views::table.on(profile_views::viewer_id
+                    .eq(viewer_id)
+                    .and(profile_views::profile_id.eq(profiles::id))),
+            )
+            .filter(profile_likes::profile_id.eq(viewer_id))
+            .filter(
+                profile_views::action
+                    .is_null()
+                    .or(profile_views::action.eq(ViewAction::Viewed.to_string())),
+            )
+            .filter(profiles::id.eq_any(Self::visible_to(viewer_id, hide_threshold)))
+            .order(profile_likes::created_at)
+            .select(Profile::as_select())
+            .load(connection)?)
+    }
+
+    /// Finds a profile by Telegram id, `@username` or profile id for the
+    /// admins, banned profiles included.
+    pub fn find_for_admin(query: &str) -> anyhow::Result<Option<Profile>> {
+        let connection = &mut establish_connection();
+        let query = query.trim();
+        let found = if let Ok(profile_id) = query.parse::<Uuid>() {
+            profiles::table.find(profile_id).into_boxed()
+        } else if let Ok(telegram_id) = query.parse::<i64>() {
+            profiles::table
+                .filter(profiles::user_id.eq(telegram_id))
+                .into_boxed()
+        } else {
+            profiles::table
+                .filter(profiles::username.eq(query.trim_start_matches('@')))
+                .into_boxed()
+        };
+        Ok(found
             .select(Profile::as_select())
             .first(connection)
-            .unwrap()
+            .optional()?)
+    }
+
+    /// Telegram ids of every profile that hasn't been banned and can still be
+    /// reached.
+    pub fn get_broadcast_user_ids() -> anyhow::Result<Vec<i64>> {
+        use crate::schema::profiles::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profiles
+            .filter(status.ne(ProfileStatus::Banned.to_string()))
+            .filter(bot_blocked.eq(false))
+            .select(user_id)
+            .load(connection)?)
     }
 
     pub fn get_by_username(msg_username: &str) -> anyhow::Result<Option<Profile>> {
@@ -125,16 +724,25 @@ impl Profile {
         let connection = &mut establish_connection();
         Ok(profiles::dsl::profiles
             .filter(username.eq(msg_username))
+            .filter(profiles::status.ne(ProfileStatus::Banned.to_string()))
             .select(Profile::as_select())
             .first(connection)
             .optional()?)
     }
+}
 
-    pub fn get_profile() -> Profile {
-        let connection = &mut establish_connection();
-        profiles::dsl::profiles
-            .select(Profile::as_select())
-            .first(connection)
-            .unwrap()
+#[cfg(test)]
+impl Profile {
+    /// Fills in what a card shows beyond onboarding, for renderer tests.
+    pub fn with_card_fields(mut self, displayed_name: &str, description: &str) -> Self {
+        self.displayed_name = displayed_name.to_string();
+        self.description = description.to_string();
+        self
+    }
+
+    pub fn with_coordinates(mut self, latitude: f64, longitude: f64) -> Self {
+        self.latitude = Some(latitude);
+        self.longitude = Some(longitude);
+        self
     }
 }
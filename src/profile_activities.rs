@@ -1,10 +1,8 @@
 use crate::pg::establish_connection;
 use crate::schema::profile_activities::activity_count;
-use crate::schema::profile_activities::dsl::profile_activities;
-use crate::schema::profile_likes::viewer_id;
+use diesel::upsert::excluded;
 use diesel::{
-    ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable, RunQueryDsl, Selectable,
-    SelectableHelper,
+    ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
 };
 use uuid::Uuid;
 
@@ -41,48 +39,29 @@ impl ProfileActivity {
         &self.viewer_id
     }
 
-    pub fn upsert_and_increment(&self) -> anyhow::Result<Self> {
-        use crate::schema::profile_activities;
-        let connection = &mut establish_connection();
-        let profile_activity = profile_activities::dsl::profile_activities
-            .find(self.viewer_id)
-            .select(ProfileActivity::as_select())
-            .first(connection)
-            .optional()?;
-        let profile = match profile_activity {
-            Some(profile) => profile,
-            None => Self::insert(self)?,
-        };
-        profile.increment()?;
-        Ok(profile)
+    pub fn activity_count(&self) -> &i32 {
+        &self.activity_count
     }
 
-    pub fn insert(&self) -> anyhow::Result<Self> {
+    /// Inserts the activity row or bumps the existing counter in a single
+    /// `INSERT … ON CONFLICT DO UPDATE`, so concurrent calls never lose an increment.
+    pub fn upsert_and_increment(&self) -> anyhow::Result<Self> {
         use crate::schema::profile_activities;
         let connection = &mut establish_connection();
 
         let activity = diesel::insert_into(profile_activities::table)
             .values(self)
+            .on_conflict(profile_activities::viewer_id)
+            .do_update()
+            .set(activity_count.eq(activity_count + excluded(activity_count)))
             .returning(ProfileActivity::as_returning())
             .get_result(connection)?;
 
         Ok(activity)
     }
 
-    pub fn increment(&self) -> anyhow::Result<()> {
-        use crate::schema::profile_activities::viewer_id;
-        let connection = &mut establish_connection();
-        let new_count = self.activity_count + 1;
-        diesel::update(profile_activities)
-            .filter(viewer_id.eq(self.viewer_id))
-            .set(activity_count.eq(new_count))
-            .execute(connection)?;
-        Ok(())
-    }
-
     pub fn get_most_active_profile() -> anyhow::Result<Self> {
         use crate::schema::profile_activities;
-        use crate::schema::profile_activities::activity_count;
         let connection = &mut establish_connection();
         let profile_activity = profile_activities::dsl::profile_activities
             .select(ProfileActivity::as_select())
@@ -91,3 +70,40 @@ impl ProfileActivity {
         Ok(profile_activity)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg::init_test_pool;
+    use crate::schema::profile_activities;
+
+    const TASKS: i32 = 50;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn concurrent_upserts_count_every_call() {
+        init_test_pool();
+        let id = Uuid::new_v4();
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|_| {
+                tokio::task::spawn_blocking(move || {
+                    ProfileActivity::from_id(id).upsert_and_increment()
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let connection = &mut establish_connection();
+        let count: i32 = profile_activities::table
+            .find(id)
+            .select(activity_count)
+            .first(connection)
+            .unwrap();
+        diesel::delete(profile_activities::table.find(id))
+            .execute(connection)
+            .unwrap();
+        assert_eq!(count, TASKS);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::pg::establish_connection;
+use diesel::dsl::exists;
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl,
+    Selectable, select,
+};
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::profile_blocks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProfileBlock {
+    blocker_id: Uuid,
+    blocked_id: Uuid,
+}
+
+impl ProfileBlock {
+    pub fn new(blocker_id: Uuid, blocked_id: Uuid) -> Self {
+        Self {
+            blocker_id,
+            blocked_id,
+        }
+    }
+
+    pub fn insert(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_blocks;
+        let connection = &mut establish_connection();
+        diesel::insert_into(profile_blocks::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn delete(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_blocks::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::delete(profile_blocks.find((self.blocker_id, self.blocked_id)))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Whether either of the two profiles has blocked the other.
+    pub fn exists_between(first: &Uuid, second: &Uuid) -> anyhow::Result<bool> {
+        use crate::schema::profile_blocks::dsl::*;
+        let connection = &mut establish_connection();
+        let result = select(exists(
+            profile_blocks.filter(
+                blocker_id
+                    .eq(first)
+                    .and(blocked_id.eq(second))
+                    .or(blocker_id.eq(second).and(blocked_id.eq(first))),
+            ),
+        ));
+        Ok(result.get_result::<bool>(connection)?)
+    }
+
+    /// Profiles the blocker has blocked, most recent first.
+    pub fn blocked_by(blocker: &Uuid) -> anyhow::Result<Vec<Uuid>> {
+        use crate::schema::profile_blocks::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_blocks
+            .filter(blocker_id.eq(blocker))
+            .order(created_at.desc())
+            .select(blocked_id)
+            .load(connection)?)
+    }
+}
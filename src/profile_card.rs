@@ -0,0 +1,294 @@
+use crate::i18n::Msg;
+use crate::outbound_job::Delivery;
+use crate::outbound_queue::OutboundQueue;
+use crate::profile::{ContactMode, Profile};
+use teloxide::payloads::{SendMessageSetters, SendPhotoSetters};
+use teloxide::prelude::*;
+use teloxide::types::{InputFile, ParseMode, ReplyMarkup, UserId};
+use teloxide::utils::html;
+
+/// Longest description shown on a card, in characters.
+pub const DESCRIPTION_LIMIT: usize = 500;
+/// Telegram limit for photo captions, in characters.
+const CAPTION_LIMIT: usize = 1024;
+/// Mean Earth radius used for distances, in kilometres.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Who is looking at the card. Decides which private fields are shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardAudience {
+    /// The profile owner previewing their own card
+    Owner,
+    /// Someone with a mutual like
+    Match,
+    /// Anyone browsing the feed
+    Stranger,
+    /// An admin reviewing reports
+    Moderator,
+}
+
+/// Renders a profile the same way everywhere it is shown, as Telegram HTML.
+pub struct ProfileCard<'a> {
+    profile: &'a Profile,
+    audience: CardAudience,
+    header: Option<String>,
+    distance: Option<String>,
+}
+
+impl<'a> ProfileCard<'a> {
+    pub fn new(profile: &'a Profile, audience: CardAudience) -> Self {
+        Self {
+            profile,
+            audience,
+            header: None,
+            distance: None,
+        }
+    }
+
+    /// Plain text shown in bold above the card.
+    pub fn with_header(mut self, header: impl Into<String>) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+
+    /// Shows how far the profile is from the viewer, in the viewer's
+    /// language, when both shared their location.
+    pub fn with_distance_from(mut self, viewer: &Profile) -> Self {
+        if let (Some(from), Some(to)) = (viewer.coordinates(), self.profile.coordinates()) {
+            let km = distance_km(from, to).round().max(1.0);
+            let lang = viewer.language().unwrap_or_default();
+            self.distance = Some(lang.f(Msg::DistanceKm, &[("km", &km)]));
+        }
+        self
+    }
+
+    /// First uploaded media, sent as the card photo.
+    pub fn media(&self) -> Option<&'a str> {
+        self.profile.file_ids().first().copied()
+    }
+
+    pub fn html(&self) -> String {
+        self.render(DESCRIPTION_LIMIT)
+    }
+
+    /// The card as a photo caption, with the description cut further until
+    /// the caption fits. `None` when even dropping the description isn't
+    /// enough.
+    fn caption(&self) -> Option<String> {
+        let fits = |limit| self.render(limit).chars().count() <= CAPTION_LIMIT;
+        if !fits(0) {
+            return None;
+        }
+        // The longest description limit that still fits
+        let (mut low, mut high) = (0, DESCRIPTION_LIMIT);
+        while low < high {
+            let middle = (low + high).div_ceil(2);
+            match fits(middle) {
+                true => low = middle,
+                false => high = middle - 1,
+            }
+        }
+        Some(self.render(low))
+    }
+
+    fn render(&self, description_limit: usize) -> String {
+        let profile = self.profile;
+        let mut lines = Vec::new();
+        if let Some(header) = &self.header {
+            lines.push(html::bold(&html::escape(header)));
+        }
+        let mut title = vec![profile.age().to_string()];
+        if !profile.displayed_name().is_empty() {
+            title.insert(0, html::bold(&html::escape(profile.displayed_name())));
+        }
+        if !profile.location().is_empty() {
+            title.push(html::escape(profile.location()));
+        }
+        if let Some(distance) = &self.distance {
+            title.push(html::escape(distance));
+        }
+        lines.push(title.join(", "));
+        match self.audience {
+            CardAudience::Owner | CardAudience::Match => lines.extend(contact_html(profile)),
+            CardAudience::Moderator => {
+                lines.push(html::escape(&format!("@{}", profile.username())));
+                lines.push(html::code_inline(&profile.user_id().to_string()));
+            }
+            CardAudience::Stranger => {}
+        }
+        if !profile.description().is_empty() && description_limit > 0 {
+            lines.push(html::escape(&truncate(
+                profile.description(),
+                description_limit,
+            )));
+        }
+        lines.join("\n")
+    }
+
+    /// Sends the card as a photo with caption when there is media, otherwise
+    /// as a text message. A description too long for a caption is shortened
+    /// so that the photo still goes out.
+    pub async fn send(
+        &self,
+        bot: &Bot,
+        chat_id: ChatId,
+        markup: Option<ReplyMarkup>,
+    ) -> anyhow::Result<Message> {
+        let message = match self.media().zip(self.caption()) {
+            Some((file_id, caption)) => {
+                let mut request = bot
+                    .send_photo(chat_id, InputFile::file_id(file_id.to_string()))
+                    .caption(caption)
+                    .parse_mode(ParseMode::Html);
+                if let Some(markup) = markup {
+                    request = request.reply_markup(markup);
+                }
+                request.await?
+            }
+            None => {
+                let mut request = bot
+                    .send_message(chat_id, self.html())
+                    .parse_mode(ParseMode::Html);
+                if let Some(markup) = markup {
+                    request = request.reply_markup(markup);
+                }
+                request.await?
+            }
+        };
+        Ok(message)
+    }
+
+    /// Like `send`, but through the throttled outbound queue, for messages
+    /// that aren't a reply to the recipient.
+    pub fn enqueue(
+        &self,
+        chat_id: ChatId,
+        markup: Option<ReplyMarkup>,
+        delivery: Delivery,
+    ) -> anyhow::Result<()> {
+        let delivery = Delivery {
+            subject_id: Some(*self.profile.id()),
+            ..delivery
+        };
+        match self.media().zip(self.caption()) {
+            Some((file_id, caption)) => {
+                OutboundQueue::photo(chat_id, file_id, caption, markup, delivery)
+            }
+            None => OutboundQueue::text(chat_id, self.html(), markup, delivery),
+        }
+    }
+}
+
+/// Short plain-text label for lists and buttons: the name and age, or just
+/// the age when no name is set.
+pub fn short_label(profile: &Profile) -> String {
+    match profile.displayed_name().is_empty() {
+        true => profile.age().to_string(),
+        false => format!("{}, {}", profile.displayed_name(), profile.age()),
+    }
+}
+
+/// Contact of the profile owner as their matches see it, `None` when the owner
+/// only talks through the bot.
+pub fn contact_html(profile: &Profile) -> Option<String> {
+    match profile.contact_mode() {
+        ContactMode::Username => Some(html::escape(&format!("@{}", profile.username()))),
+        ContactMode::DeepLink => {
+            let text = match profile.displayed_name().is_empty() {
+                true => "Telegram",
+                false => profile.displayed_name(),
+            };
+            Some(html::user_mention(UserId(*profile.user_id() as u64), text))
+        }
+        ContactMode::Relay => None,
+    }
+}
+
+/// Cuts `text` to at most `limit` characters, marking the cut with an ellipsis.
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(limit.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// Great-circle distance between two `(latitude, longitude)` points.
+fn distance_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Lang;
+
+    fn profile(description: &str) -> Profile {
+        Profile::new(123456789, Some("secret_user".to_string()), Lang::En)
+            .with_card_fields("<b>Tom & Jerry</b>", description)
+    }
+
+    #[test]
+    fn escapes_html_in_user_text() {
+        let card = ProfileCard::new(&profile("a < b"), CardAudience::Stranger).html();
+        assert!(card.contains("&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"));
+        assert!(card.contains("a &lt; b"));
+        assert!(!card.contains("<b>Tom"));
+    }
+
+    #[test]
+    fn truncates_long_descriptions() {
+        let card = ProfileCard::new(&profile(&"x".repeat(600)), CardAudience::Stranger).html();
+        assert!(card.contains(&format!("{}…", "x".repeat(DESCRIPTION_LIMIT - 1))));
+        assert!(!card.contains(&"x".repeat(DESCRIPTION_LIMIT)));
+    }
+
+    #[test]
+    fn shortens_the_description_so_the_caption_fits() {
+        // Every `&` takes five characters once escaped
+        let profile = profile(&"&".repeat(DESCRIPTION_LIMIT));
+        let card = ProfileCard::new(&profile, CardAudience::Stranger);
+        assert!(card.html().chars().count() > CAPTION_LIMIT);
+        let caption = card.caption().unwrap();
+        assert!(caption.chars().count() <= CAPTION_LIMIT);
+        assert!(caption.ends_with("&amp;…"));
+    }
+
+    #[test]
+    fn hides_username_and_user_id_from_strangers() {
+        let profile = profile("");
+        let stranger = ProfileCard::new(&profile, CardAudience::Stranger).html();
+        assert!(!stranger.contains("secret_user"));
+        assert!(!stranger.contains("123456789"));
+        let matched = ProfileCard::new(&profile, CardAudience::Match).html();
+        assert!(matched.contains("@secret_user"));
+        assert!(!matched.contains("123456789"));
+        let moderator = ProfileCard::new(&profile, CardAudience::Moderator).html();
+        assert!(moderator.contains("@secret_user"));
+        assert!(moderator.contains("123456789"));
+    }
+
+    #[test]
+    fn shows_the_distance_when_both_shared_their_location() {
+        let moscow = profile("").with_coordinates(55.7558, 37.6173);
+        let petersburg = profile("").with_coordinates(59.9343, 30.3351);
+        let km = distance_km(
+            moscow.coordinates().unwrap(),
+            petersburg.coordinates().unwrap(),
+        );
+        assert!((630.0..640.0).contains(&km), "{km}");
+        let card = ProfileCard::new(&petersburg, CardAudience::Stranger)
+            .with_distance_from(&moscow)
+            .html();
+        assert!(card.contains("633 km away"));
+        let card = ProfileCard::new(&petersburg, CardAudience::Stranger)
+            .with_distance_from(&profile(""))
+            .html();
+        assert!(!card.contains("km away"));
+    }
+}
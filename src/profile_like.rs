@@ -0,0 +1,76 @@
+use crate::pg::establish_connection;
+use diesel::dsl::exists;
+use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, select};
+use uuid::Uuid;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::profile_likes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProfileLike {
+    viewer_id: Uuid,
+    profile_id: Uuid,
+}
+
+impl ProfileLike {
+    pub fn new(viewer_id: Uuid, profile_id: Uuid) -> Self {
+        Self {
+            viewer_id,
+            profile_id,
+        }
+    }
+
+    pub fn insert(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_likes;
+        let connection = &mut establish_connection();
+        diesel::insert_into(profile_likes::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+        Ok(())
+    }
+
+    pub fn delete(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_likes::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::delete(profile_likes.find((self.viewer_id, self.profile_id)))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Profiles the viewer has liked.
+    pub fn liked_by(viewer: &Uuid) -> anyhow::Result<Vec<Uuid>> {
+        use crate::schema::profile_likes::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_likes
+            .filter(viewer_id.eq(viewer))
+            .select(profile_id)
+            .load(connection)?)
+    }
+
+    /// How many likes the profile gave and received.
+    pub fn counts_for(profile: &Uuid) -> anyhow::Result<(i64, i64)> {
+        use crate::schema::profile_likes::dsl::*;
+        let connection = &mut establish_connection();
+        let given = profile_likes
+            .filter(viewer_id.eq(profile))
+            .count()
+            .get_result(connection)?;
+        let received = profile_likes
+            .filter(profile_id.eq(profile))
+            .count()
+            .get_result(connection)?;
+        Ok((given, received))
+    }
+
+    /// Whether the liked profile has liked the viewer back.
+    pub fn is_mutual(&self) -> anyhow::Result<bool> {
+        use crate::schema::profile_likes::dsl::*;
+        let connection = &mut establish_connection();
+        let result = select(exists(
+            profile_likes
+                .filter(viewer_id.eq(self.profile_id))
+                .filter(profile_id.eq(self.viewer_id)),
+        ));
+        Ok(result.get_result::<bool>(connection)?)
+    }
+}
@@ -0,0 +1,215 @@
+use crate::pg::establish_connection;
+use chrono::{Duration, Utc};
+use diesel::dsl::IntervalDsl;
+use diesel::result::Error as DieselError;
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable,
+    RunQueryDsl, Selectable, SelectableHelper,
+};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Active matches without a relayed message for this long are expired.
+pub const EXPIRY_DAYS: i64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchStatus {
+    Active,
+    /// Ended by the profile in `profile_a`
+    UnmatchedByA,
+    /// Ended by the profile in `profile_b`
+    UnmatchedByB,
+    /// Ended after a long silence
+    Expired,
+}
+impl fmt::Display for MatchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchStatus::Active => write!(f, "ACTIVE"),
+            MatchStatus::UnmatchedByA => write!(f, "UNMATCHED_BY_A"),
+            MatchStatus::UnmatchedByB => write!(f, "UNMATCHED_BY_B"),
+            MatchStatus::Expired => write!(f, "EXPIRED"),
+        }
+    }
+}
+impl FromStr for MatchStatus {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let status = match s {
+            "ACTIVE" => Self::Active,
+            "UNMATCHED_BY_A" => Self::UnmatchedByA,
+            "UNMATCHED_BY_B" => Self::UnmatchedByB,
+            "EXPIRED" => Self::Expired,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(status)
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::profile_matches)]
+struct NewProfileMatch {
+    profile_a: Uuid,
+    profile_b: Uuid,
+}
+
+/// Two profiles that liked each other. The pair is stored once, with the
+/// smaller id in `profile_a`, and the row is kept after the match ends.
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::profile_matches)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProfileMatch {
+    id: Uuid,
+    profile_a: Uuid,
+    profile_b: Uuid,
+    status: String,
+}
+
+impl ProfileMatch {
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub fn status(&self) -> MatchStatus {
+        self.status.parse().unwrap_or(MatchStatus::Active)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status() == MatchStatus::Active
+    }
+
+    pub fn includes(&self, profile: &Uuid) -> bool {
+        self.profile_a == *profile || self.profile_b == *profile
+    }
+
+    /// The other side of the match for one of its members.
+    pub fn other(&self, profile: &Uuid) -> Uuid {
+        match self.profile_a == *profile {
+            true => self.profile_b,
+            false => self.profile_a,
+        }
+    }
+
+    /// Stores the match between two profiles, or returns the existing one
+    /// whatever its status.
+    pub fn create(first: Uuid, second: Uuid) -> anyhow::Result<ProfileMatch> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        let pair = NewProfileMatch {
+            profile_a: first.min(second),
+            profile_b: first.max(second),
+        };
+        diesel::insert_into(profile_matches)
+            .values(&pair)
+            .on_conflict((profile_a, profile_b))
+            .do_nothing()
+            .execute(connection)?;
+        Ok(profile_matches
+            .filter(profile_a.eq(pair.profile_a))
+            .filter(profile_b.eq(pair.profile_b))
+            .select(ProfileMatch::as_select())
+            .first(connection)?)
+    }
+
+    /// Removes the still active match between the pair if it was created
+    /// within the last `window`, returning its id. Older matches are left
+    /// alone.
+    pub fn delete_created_within(
+        first: &Uuid,
+        second: &Uuid,
+        window: std::time::Duration,
+    ) -> anyhow::Result<Option<Uuid>> {
+        use crate::schema::profile_matches::dsl::*;
+        let window_secs = i64::try_from(window.as_secs())?;
+        let connection = &mut establish_connection();
+        Ok(diesel::delete(
+            profile_matches
+                .filter(profile_a.eq(first.min(second)))
+                .filter(profile_b.eq(first.max(second)))
+                .filter(status.eq(MatchStatus::Active.to_string()))
+                .filter(created_at.ge(diesel::dsl::now - window_secs.seconds())),
+        )
+        .returning(id)
+        .get_result(connection)
+        .optional()?)
+    }
+
+    pub fn get(match_id: &Uuid) -> anyhow::Result<Option<ProfileMatch>> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_matches
+            .find(match_id)
+            .select(ProfileMatch::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    /// Active matches of the profile, most recent first. Stale ones are
+    /// expired on the way.
+    pub fn for_profile(profile: &Uuid) -> anyhow::Result<Vec<ProfileMatch>> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        let now = Utc::now().naive_utc();
+        diesel::update(
+            profile_matches
+                .filter(profile_a.eq(profile).or(profile_b.eq(profile)))
+                .filter(status.eq(MatchStatus::Active.to_string()))
+                .filter(last_active_at.lt(now - Duration::days(EXPIRY_DAYS))),
+        )
+        .set((
+            status.eq(MatchStatus::Expired.to_string()),
+            ended_at.eq(now),
+        ))
+        .execute(connection)?;
+        Ok(profile_matches
+            .filter(profile_a.eq(profile).or(profile_b.eq(profile)))
+            .filter(status.eq(MatchStatus::Active.to_string()))
+            .order(created_at.desc())
+            .select(ProfileMatch::as_select())
+            .load(connection)?)
+    }
+
+    /// Every match the profile has been in, ended ones included.
+    pub fn history_for(profile: &Uuid) -> anyhow::Result<Vec<ProfileMatch>> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_matches
+            .filter(profile_a.eq(profile).or(profile_b.eq(profile)))
+            .order(created_at.desc())
+            .select(ProfileMatch::as_select())
+            .load(connection)?)
+    }
+
+    /// Marks the match as still alive, pushing back its expiry.
+    pub fn touch(match_id: &Uuid) -> anyhow::Result<()> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profile_matches.find(match_id))
+            .set(last_active_at.eq(Utc::now().naive_utc()))
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Ends an active match on behalf of one of its members.
+    pub fn unmatch(&self, by: &Uuid) -> anyhow::Result<()> {
+        use crate::schema::profile_matches::dsl::*;
+        let connection = &mut establish_connection();
+        let new_status = match self.profile_a == *by {
+            true => MatchStatus::UnmatchedByA,
+            false => MatchStatus::UnmatchedByB,
+        };
+        diesel::update(
+            profile_matches
+                .find(self.id)
+                .filter(status.eq(MatchStatus::Active.to_string())),
+        )
+        .set((
+            status.eq(new_status.to_string()),
+            ended_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(connection)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use crate::pg::establish_connection;
+use chrono::NaiveDateTime;
+use diesel::dsl::count_star;
+use diesel::result::Error as DieselError;
+use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportReason {
+    Spam,
+    Fake,
+    Underage,
+    Offensive,
+}
+impl ReportReason {
+    pub const ALL: [ReportReason; 4] = [
+        ReportReason::Spam,
+        ReportReason::Fake,
+        ReportReason::Underage,
+        ReportReason::Offensive,
+    ];
+}
+impl fmt::Display for ReportReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportReason::Spam => write!(f, "SPAM"),
+            ReportReason::Fake => write!(f, "FAKE"),
+            ReportReason::Underage => write!(f, "UNDERAGE"),
+            ReportReason::Offensive => write!(f, "OFFENSIVE"),
+        }
+    }
+}
+impl FromStr for ReportReason {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let reason = match s {
+            "SPAM" => Self::Spam,
+            "FAKE" => Self::Fake,
+            "UNDERAGE" => Self::Underage,
+            "OFFENSIVE" => Self::Offensive,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(reason)
+    }
+}
+
+/// Where a report is in the moderation queue. Dismissed reports no longer
+/// count towards hiding the profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportStatus {
+    Open,
+    Dismissed,
+    Actioned,
+}
+impl ReportStatus {
+    /// Reports that keep counting towards the auto-hide threshold.
+    pub const COUNTED: [ReportStatus; 2] = [ReportStatus::Open, ReportStatus::Actioned];
+}
+impl fmt::Display for ReportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportStatus::Open => write!(f, "OPEN"),
+            ReportStatus::Dismissed => write!(f, "DISMISSED"),
+            ReportStatus::Actioned => write!(f, "ACTIONED"),
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::profile_reports)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProfileReport {
+    reporter_id: Uuid,
+    profile_id: Uuid,
+    reason: String,
+}
+
+impl ProfileReport {
+    pub fn new(reporter_id: Uuid, profile_id: Uuid, reason: ReportReason) -> Self {
+        Self {
+            reporter_id,
+            profile_id,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Stores the report. A second report of the same profile by the same
+    /// reporter is ignored.
+    pub fn insert(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_reports;
+        let connection = &mut establish_connection();
+        diesel::insert_into(profile_reports::table)
+            .values(self)
+            .on_conflict((profile_reports::reporter_id, profile_reports::profile_id))
+            .do_nothing()
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Reports filed by the reporter as `(profile_id, reason, status, created_at)`,
+    /// oldest first.
+    pub fn filed_by(reporter: &Uuid) -> anyhow::Result<Vec<(Uuid, String, String, NaiveDateTime)>> {
+        use crate::schema::profile_reports::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_reports
+            .filter(reporter_id.eq(reporter))
+            .order(created_at.asc())
+            .select((profile_id, reason, status, created_at))
+            .load(connection)?)
+    }
+
+    /// Reports against the profile that count towards hiding it.
+    pub fn counted_for(reported: &Uuid) -> anyhow::Result<i64> {
+        use crate::schema::profile_reports::dsl::*;
+        let counted: Vec<String> = ReportStatus::COUNTED
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let connection = &mut establish_connection();
+        Ok(profile_reports
+            .filter(profile_id.eq(reported))
+            .filter(status.eq_any(counted))
+            .count()
+            .get_result(connection)?)
+    }
+
+    /// Profiles with open reports, most reported first, with their report count.
+    pub fn open_queue(limit: i64) -> anyhow::Result<Vec<(Uuid, i64)>> {
+        use crate::schema::profile_reports::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(profile_reports
+            .filter(status.eq(ReportStatus::Open.to_string()))
+            .group_by(profile_id)
+            .select((profile_id, count_star()))
+            .order(count_star().desc())
+            .limit(limit)
+            .load(connection)?)
+    }
+
+    /// Reasons of the open reports against a profile.
+    pub fn open_reasons(reported_id: &Uuid) -> anyhow::Result<Vec<ReportReason>> {
+        use crate::schema::profile_reports::dsl::*;
+        let connection = &mut establish_connection();
+        let reasons = profile_reports
+            .filter(profile_id.eq(reported_id))
+            .filter(status.eq(ReportStatus::Open.to_string()))
+            .select(reason)
+            .load::<String>(connection)?;
+        Ok(reasons
+            .iter()
+            .filter_map(|value| value.parse().ok())
+            .collect())
+    }
+
+    /// Closes every open report against a profile with the given status.
+    pub fn resolve(reported_id: &Uuid, new_status: ReportStatus) -> anyhow::Result<usize> {
+        use crate::schema::profile_reports::dsl::*;
+        let connection = &mut establish_connection();
+        Ok(diesel::update(profile_reports)
+            .filter(profile_id.eq(reported_id))
+            .filter(status.eq(ReportStatus::Open.to_string()))
+            .set(status.eq(new_status.to_string()))
+            .execute(connection)?)
+    }
+}
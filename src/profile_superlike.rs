@@ -0,0 +1,70 @@
+use crate::pg::establish_connection;
+use diesel::dsl::{IntervalDsl, now};
+use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable};
+use uuid::Uuid;
+
+/// Superlikes anyone may send within 24 hours unless configured otherwise.
+pub const DEFAULT_DAILY_SUPERLIKES: i64 = 1;
+/// Longest text note, in characters.
+pub const NOTE_LIMIT: usize = 300;
+
+/// A like that reaches the other side right away, with an optional text or
+/// voice note.
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::profile_superlikes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ProfileSuperlike {
+    viewer_id: Uuid,
+    profile_id: Uuid,
+    message: Option<String>,
+    voice_file_id: Option<String>,
+}
+
+impl ProfileSuperlike {
+    pub fn new(
+        viewer_id: Uuid,
+        profile_id: Uuid,
+        message: Option<String>,
+        voice_file_id: Option<String>,
+    ) -> Self {
+        Self {
+            viewer_id,
+            profile_id,
+            message,
+            voice_file_id,
+        }
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub fn voice_file_id(&self) -> Option<&str> {
+        self.voice_file_id.as_deref()
+    }
+
+    /// Stores the superlike. Returns `false` when the profile was superliked
+    /// by the same viewer before.
+    pub fn insert(&self) -> anyhow::Result<bool> {
+        use crate::schema::profile_superlikes;
+        let connection = &mut establish_connection();
+        let inserted = diesel::insert_into(profile_superlikes::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+        Ok(inserted > 0)
+    }
+
+    /// Superlikes the viewer can still send out of the daily `allowance`,
+    /// counted over the last 24 hours.
+    pub fn remaining_today(viewer: &Uuid, allowance: i64) -> anyhow::Result<i64> {
+        use crate::schema::profile_superlikes::dsl::*;
+        let connection = &mut establish_connection();
+        let sent: i64 = profile_superlikes
+            .filter(viewer_id.eq(viewer))
+            .filter(created_at.gt(now - 1.days()))
+            .count()
+            .get_result(connection)?;
+        Ok((allowance - sent).max(0))
+    }
+}
@@ -1,17 +1,56 @@
 use crate::pg::establish_connection;
-use diesel::dsl::exists;
-use diesel::{
-    ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
-    select,
-};
+use diesel::dsl::{IntervalDsl, exists, now};
+use diesel::result::Error as DieselError;
+use diesel::{ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, select};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// What the viewer did with a profile they were shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewAction {
+    Viewed,
+    Skipped,
+    Liked,
+    Reported,
+}
+impl ViewAction {
+    /// Views that may be shown again once the cooldown has passed.
+    pub const RECYCLABLE: [ViewAction; 2] = [ViewAction::Viewed, ViewAction::Skipped];
+}
+impl fmt::Display for ViewAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewAction::Viewed => write!(f, "VIEWED"),
+            ViewAction::Skipped => write!(f, "SKIPPED"),
+            ViewAction::Liked => write!(f, "LIKED"),
+            ViewAction::Reported => write!(f, "REPORTED"),
+        }
+    }
+}
+impl FromStr for ViewAction {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let action = match s {
+            "VIEWED" => Self::Viewed,
+            "SKIPPED" => Self::Skipped,
+            "LIKED" => Self::Liked,
+            "REPORTED" => Self::Reported,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(action)
+    }
+}
+
 #[derive(Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::schema::profile_views)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct ProfileView {
     viewer_id: Uuid,
     profile_id: Uuid,
+    action: String,
 }
 
 impl ProfileView {
@@ -19,26 +58,139 @@ impl ProfileView {
         Self {
             viewer_id,
             profile_id,
+            action: ViewAction::Viewed.to_string(),
         }
     }
 
-    pub fn insert(&self) -> anyhow::Result<Self> {
-        let connection = &mut establish_connection();
+    /// Records the view. Showing the same profile again only refreshes
+    /// `viewed_at`, so an earlier like or report is kept.
+    pub fn insert(&self) -> anyhow::Result<()> {
         use crate::schema::profile_views;
-        let profile_view = diesel::insert_into(profile_views::table)
+        let connection = &mut establish_connection();
+        diesel::insert_into(profile_views::table)
             .values(self)
-            .returning(ProfileView::as_returning())
-            .get_result(connection)?;
+            .on_conflict((profile_views::viewer_id, profile_views::profile_id))
+            .do_update()
+            .set(profile_views::viewed_at.eq(now))
+            .execute(connection)?;
+
+        Ok(())
+    }
+
+    pub fn set_action(&self, view_action: ViewAction) -> anyhow::Result<()> {
+        use crate::schema::profile_views::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::update(profile_views)
+            .filter(viewer_id.eq(self.viewer_id))
+            .filter(profile_id.eq(self.profile_id))
+            .set(action.eq(view_action.to_string()))
+            .execute(connection)?;
+        Ok(())
+    }
 
-        Ok(profile_view)
+    pub fn delete(&self) -> anyhow::Result<()> {
+        use crate::schema::profile_views::dsl::*;
+        let connection = &mut establish_connection();
+        diesel::delete(profile_views.find((self.viewer_id, self.profile_id)))
+            .execute(connection)?;
+        Ok(())
     }
 
     pub fn exists(&self) -> anyhow::Result<bool> {
         use crate::schema::profile_views::dsl::*;
-        use diesel::prelude::*;
-        use diesel::select;
         let connection = &mut establish_connection();
-        let result = select(exists(profile_views.filter(viewer_id.eq(self.viewer_id))));
+        let result = select(exists(
+            profile_views
+                .filter(viewer_id.eq(self.viewer_id))
+                .filter(profile_id.eq(self.profile_id)),
+        ));
         Ok(result.get_result::<bool>(connection)?)
     }
+
+    /// Returns the subset of `candidates` the viewer has already seen.
+    pub fn viewed_set(viewer: &Uuid, candidates: &[Uuid]) -> anyhow::Result<HashSet<Uuid>> {
+        use crate::schema::profile_views::dsl::*;
+        if candidates.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let connection = &mut establish_connection();
+        let viewed = profile_views
+            .filter(viewer_id.eq(viewer))
+            .filter(profile_id.eq_any(candidates))
+            .select(profile_id)
+            .load::<Uuid>(connection)?;
+        Ok(viewed.into_iter().collect())
+    }
+
+    /// Forgets the viewer's skipped views older than `cooldown_days`, so those
+    /// profiles can be shown again. Liked and reported profiles are kept.
+    /// Returns the number of recycled profiles.
+    pub fn reset_for(viewer: &Uuid, cooldown_days: i64) -> anyhow::Result<usize> {
+        use crate::schema::profile_views::dsl::*;
+        let recyclable: Vec<String> = ViewAction::RECYCLABLE
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let connection = &mut establish_connection();
+        let deleted = diesel::delete(
+            profile_views
+                .filter(viewer_id.eq(viewer))
+                .filter(viewed_at.lt(now - cooldown_days.days()))
+                .filter(action.eq_any(recyclable)),
+        )
+        .execute(connection)?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pg::init_test_pool;
+    use crate::schema::profile_views;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn concurrent_inserts_of_the_same_view_keep_one_row() {
+        init_test_pool();
+        let (viewer, profile) = (Uuid::new_v4(), Uuid::new_v4());
+        let tasks: Vec<_> = (0..50)
+            .map(|_| {
+                tokio::task::spawn_blocking(move || ProfileView::new(viewer, profile).insert())
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let connection = &mut establish_connection();
+        let rows: i64 = profile_views::table
+            .filter(profile_views::viewer_id.eq(viewer))
+            .count()
+            .get_result(connection)
+            .unwrap();
+        diesel::delete(profile_views::table.filter(profile_views::viewer_id.eq(viewer)))
+            .execute(connection)
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    fn showing_a_profile_again_keeps_the_action() {
+        init_test_pool();
+        let view = ProfileView::new(Uuid::new_v4(), Uuid::new_v4());
+        view.insert().unwrap();
+        view.set_action(ViewAction::Liked).unwrap();
+        view.insert().unwrap();
+
+        let connection = &mut establish_connection();
+        let stored: String = profile_views::table
+            .find((view.viewer_id, view.profile_id))
+            .select(profile_views::action)
+            .get_result(connection)
+            .unwrap();
+        view.delete().unwrap();
+        assert_eq!(stored, ViewAction::Liked.to_string());
+    }
 }
@@ -0,0 +1,27 @@
+use crate::profile_view::ProfileView;
+use uuid::Uuid;
+
+pub const DEFAULT_COOLDOWN_DAYS: i64 = 7;
+
+/// Decides when skipped profiles become eligible for the feed again.
+pub struct RecyclePolicy {
+    cooldown_days: i64,
+}
+
+impl Default for RecyclePolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_COOLDOWN_DAYS)
+    }
+}
+
+impl RecyclePolicy {
+    pub fn new(cooldown_days: i64) -> Self {
+        Self { cooldown_days }
+    }
+
+    /// Makes the viewer's skipped profiles older than the cooldown visible again.
+    /// Returns the number of recycled profiles.
+    pub fn recycle(&self, viewer_id: &Uuid) -> anyhow::Result<usize> {
+        ProfileView::reset_for(viewer_id, self.cooldown_days)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::pg::establish_connection;
+use diesel::dsl::{IntervalDsl, now};
+use diesel::result::Error as DieselError;
+use diesel::{ExpressionMethods, Insertable, QueryDsl, RunQueryDsl};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long relayed messages are kept before they are pruned.
+pub const RETENTION_DAYS: i64 = 30;
+/// How often messages past the retention period are pruned.
+pub const PRUNE_EVERY: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelayKind {
+    Text,
+    Photo,
+    Voice,
+}
+impl fmt::Display for RelayKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayKind::Text => write!(f, "TEXT"),
+            RelayKind::Photo => write!(f, "PHOTO"),
+            RelayKind::Voice => write!(f, "VOICE"),
+        }
+    }
+}
+impl FromStr for RelayKind {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s {
+            "TEXT" => Self::Text,
+            "PHOTO" => Self::Photo,
+            "VOICE" => Self::Voice,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(kind)
+    }
+}
+
+/// A message passed between two matches through the bot.
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::relay_messages)]
+pub struct RelayMessage {
+    match_id: Uuid,
+    sender_id: Uuid,
+    kind: String,
+    body: Option<String>,
+    file_id: Option<String>,
+}
+
+impl RelayMessage {
+    pub fn new(
+        match_id: Uuid,
+        sender_id: Uuid,
+        kind: RelayKind,
+        body: Option<String>,
+        file_id: Option<String>,
+    ) -> Self {
+        Self {
+            match_id,
+            sender_id,
+            kind: kind.to_string(),
+            body,
+            file_id,
+        }
+    }
+
+    pub fn insert(&self) -> anyhow::Result<()> {
+        use crate::schema::relay_messages;
+        let connection = &mut establish_connection();
+        diesel::insert_into(relay_messages::table)
+            .values(self)
+            .execute(connection)?;
+        Ok(())
+    }
+
+    /// Drops the messages past the retention period. Returns how many.
+    pub fn prune_expired() -> anyhow::Result<usize> {
+        use crate::schema::relay_messages::dsl::*;
+        let connection = &mut establish_connection();
+        let expired = created_at.lt(now - RETENTION_DAYS.days());
+        Ok(diesel::delete(relay_messages.filter(expired)).execute(connection)?)
+    }
+}
@@ -1,5 +1,37 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    active_days (user_id, day) {
+        user_id -> Int8,
+        day -> Date,
+    }
+}
+
+diesel::table! {
+    deleted_profiles (user_hash) {
+        user_hash -> Bytea,
+        profile_id -> Uuid,
+        status -> Text,
+        deleted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    outbound_jobs (id) {
+        id -> Int8,
+        chat_id -> Int8,
+        kind -> Text,
+        body -> Nullable<Text>,
+        file_id -> Nullable<Text>,
+        reply_markup -> Nullable<Text>,
+        attempts -> Int4,
+        run_after -> Timestamp,
+        created_at -> Timestamp,
+        match_id -> Nullable<Uuid>,
+        subject_id -> Nullable<Uuid>,
+    }
+}
+
 diesel::table! {
     profile_activities (viewer_id) {
         viewer_id -> Uuid,
@@ -7,10 +39,42 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    profile_blocks (blocker_id, blocked_id) {
+        blocker_id -> Uuid,
+        blocked_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     profile_likes (viewer_id, profile_id) {
         viewer_id -> Uuid,
         profile_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    profile_matches (id) {
+        id -> Uuid,
+        profile_a -> Uuid,
+        profile_b -> Uuid,
+        created_at -> Timestamp,
+        status -> Text,
+        last_active_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    profile_reports (id) {
+        id -> Uuid,
+        reporter_id -> Uuid,
+        profile_id -> Uuid,
+        reason -> Text,
+        status -> Text,
+        created_at -> Timestamp,
     }
 }
 
@@ -18,6 +82,9 @@ diesel::table! {
     profile_superlikes (viewer_id, profile_id) {
         viewer_id -> Uuid,
         profile_id -> Uuid,
+        message -> Nullable<Text>,
+        voice_file_id -> Nullable<Text>,
+        created_at -> Timestamp,
     }
 }
 
@@ -25,6 +92,8 @@ diesel::table! {
     profile_views (viewer_id, profile_id) {
         viewer_id -> Uuid,
         profile_id -> Uuid,
+        viewed_at -> Timestamp,
+        action -> Text,
     }
 }
 
@@ -41,13 +110,52 @@ diesel::table! {
         location -> Text,
         gender -> Text,
         interests -> Text,
+        status -> Text,
+        language -> Nullable<Text>,
+        contact_mode -> Text,
+        do_not_disturb -> Bool,
+        timezone -> Text,
+        bot_blocked -> Bool,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
     }
 }
 
+diesel::table! {
+    relay_messages (id) {
+        id -> Uuid,
+        match_id -> Uuid,
+        sender_id -> Uuid,
+        kind -> Text,
+        body -> Nullable<Text>,
+        file_id -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    sent_nudges (profile_id, kind, subject_id) {
+        profile_id -> Uuid,
+        kind -> Text,
+        subject_id -> Uuid,
+        sent_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(relay_messages -> profile_matches (match_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    active_days,
+    deleted_profiles,
+    outbound_jobs,
     profile_activities,
+    profile_blocks,
     profile_likes,
+    profile_matches,
+    profile_reports,
     profile_superlikes,
     profile_views,
     profiles,
+    relay_messages,
+    sent_nudges,
 );
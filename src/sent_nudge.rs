@@ -0,0 +1,67 @@
+use crate::pg::establish_connection;
+use diesel::result::Error as DieselError;
+use diesel::{Insertable, RunQueryDsl};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NudgeKind {
+    /// Onboarding was left half way, the subject is the profile itself
+    IncompleteProfile,
+    /// Nobody has written in a match yet, the subject is the match
+    UnansweredMatch,
+    /// Someone liked the profile, the subject is the liker
+    UnseenLike,
+}
+impl fmt::Display for NudgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NudgeKind::IncompleteProfile => write!(f, "INCOMPLETE_PROFILE"),
+            NudgeKind::UnansweredMatch => write!(f, "UNANSWERED_MATCH"),
+            NudgeKind::UnseenLike => write!(f, "UNSEEN_LIKE"),
+        }
+    }
+}
+impl FromStr for NudgeKind {
+    type Err = DieselError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s {
+            "INCOMPLETE_PROFILE" => Self::IncompleteProfile,
+            "UNANSWERED_MATCH" => Self::UnansweredMatch,
+            "UNSEEN_LIKE" => Self::UnseenLike,
+            _ => return Err(DieselError::NotFound),
+        };
+        Ok(kind)
+    }
+}
+
+/// A reminder that has been sent. Each subject is nudged about once.
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::sent_nudges)]
+pub struct SentNudge {
+    profile_id: Uuid,
+    kind: String,
+    subject_id: Uuid,
+}
+
+impl SentNudge {
+    pub fn new(profile_id: Uuid, kind: NudgeKind, subject_id: Uuid) -> Self {
+        Self {
+            profile_id,
+            kind: kind.to_string(),
+            subject_id,
+        }
+    }
+
+    pub fn insert(&self) -> anyhow::Result<()> {
+        use crate::schema::sent_nudges;
+        let connection = &mut establish_connection();
+        diesel::insert_into(sent_nudges::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(connection)?;
+        Ok(())
+    }
+}
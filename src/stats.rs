@@ -0,0 +1,127 @@
+use crate::pg::establish_connection;
+use chrono::{Duration, NaiveDate};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+// The views are not picked up by `diesel print_schema`, so they are declared
+// here instead of in schema.rs.
+diesel::table! {
+    stats_daily_active (day) {
+        day -> Date,
+        users -> Int8,
+    }
+}
+
+diesel::table! {
+    stats_daily_profiles (day) {
+        day -> Date,
+        profiles -> Int8,
+    }
+}
+
+diesel::table! {
+    stats_daily_likes (day) {
+        day -> Date,
+        likes -> Int8,
+    }
+}
+
+diesel::table! {
+    stats_daily_matches (day) {
+        day -> Date,
+        matches -> Int8,
+    }
+}
+
+diesel::table! {
+    stats_genders (gender) {
+        gender -> Text,
+        profiles -> Int8,
+    }
+}
+
+diesel::table! {
+    stats_onboarding (step) {
+        step -> Text,
+        profiles -> Int8,
+    }
+}
+
+/// How many days the weekly figures cover, today included.
+pub const WEEK_DAYS: i64 = 7;
+
+/// A snapshot of the admin dashboard.
+pub struct Stats {
+    pub active_today: i64,
+    pub profiles_today: i64,
+    pub profiles_week: i64,
+    pub likes_today: i64,
+    pub likes_week: i64,
+    pub matches_week: i64,
+    /// Profile count per gender, largest first
+    pub genders: Vec<(String, i64)>,
+    /// Profile count per onboarding state the users stopped at
+    pub onboarding: Vec<(String, i64)>,
+}
+
+impl Stats {
+    pub fn collect() -> anyhow::Result<Stats> {
+        let connection = &mut establish_connection();
+        // The views group by the database's dates, so today is taken from there
+        let today: NaiveDate = diesel::select(diesel::dsl::today).get_result(connection)?;
+        let week_start = today - Duration::days(WEEK_DAYS - 1);
+
+        let active = stats_daily_active::table
+            .filter(stats_daily_active::day.ge(week_start))
+            .select((stats_daily_active::day, stats_daily_active::users))
+            .load::<(NaiveDate, i64)>(connection)?;
+        let profiles = stats_daily_profiles::table
+            .filter(stats_daily_profiles::day.ge(week_start))
+            .select((stats_daily_profiles::day, stats_daily_profiles::profiles))
+            .load::<(NaiveDate, i64)>(connection)?;
+        let likes = stats_daily_likes::table
+            .filter(stats_daily_likes::day.ge(week_start))
+            .select((stats_daily_likes::day, stats_daily_likes::likes))
+            .load::<(NaiveDate, i64)>(connection)?;
+        let matches = stats_daily_matches::table
+            .filter(stats_daily_matches::day.ge(week_start))
+            .select((stats_daily_matches::day, stats_daily_matches::matches))
+            .load::<(NaiveDate, i64)>(connection)?;
+        let genders = stats_genders::table
+            .order(stats_genders::profiles.desc())
+            .select((stats_genders::gender, stats_genders::profiles))
+            .load(connection)?;
+        let onboarding = stats_onboarding::table
+            .order(stats_onboarding::profiles.desc())
+            .select((stats_onboarding::step, stats_onboarding::profiles))
+            .load(connection)?;
+
+        Ok(Stats {
+            active_today: on_day(&active, today),
+            profiles_today: on_day(&profiles, today),
+            profiles_week: total(&profiles),
+            likes_today: on_day(&likes, today),
+            likes_week: total(&likes),
+            matches_week: total(&matches),
+            genders,
+            onboarding,
+        })
+    }
+
+    /// Share of the week's likes that turned into a match, in percent.
+    pub fn match_rate(&self) -> f64 {
+        match self.likes_week {
+            0 => 0.0,
+            likes => self.matches_week as f64 * 100.0 / likes as f64,
+        }
+    }
+}
+
+fn on_day(rows: &[(NaiveDate, i64)], day: NaiveDate) -> i64 {
+    rows.iter()
+        .find(|(row_day, _)| *row_day == day)
+        .map_or(0, |(_, count)| *count)
+}
+
+fn total(rows: &[(NaiveDate, i64)]) -> i64 {
+    rows.iter().map(|(_, count)| count).sum()
+}
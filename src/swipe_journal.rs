@@ -0,0 +1,88 @@
+use crate::profile_view::ViewAction;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use teloxide::types::ChatId;
+use uuid::Uuid;
+
+/// How long a swipe can be taken back. Match notifications wait this long too.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy)]
+pub struct Swipe {
+    pub profile_id: Uuid,
+    pub action: ViewAction,
+    at: Instant,
+}
+
+/// Remembers the last like or skip per chat so it can be undone.
+#[derive(Default)]
+pub struct SwipeJournal {
+    last: Mutex<HashMap<ChatId, Swipe>>,
+}
+
+impl SwipeJournal {
+    pub fn record(&self, chat_id: ChatId, profile_id: Uuid, action: ViewAction) {
+        self.record_at(chat_id, profile_id, action, Instant::now());
+    }
+
+    fn record_at(&self, chat_id: ChatId, profile_id: Uuid, action: ViewAction, now: Instant) {
+        let swipe = Swipe {
+            profile_id,
+            action,
+            at: now,
+        };
+        self.last.lock().unwrap().insert(chat_id, swipe);
+    }
+
+    /// Removes the last swipe and returns it if it's still within the undo
+    /// window.
+    pub fn take(&self, chat_id: ChatId) -> Option<Swipe> {
+        self.take_at(chat_id, Instant::now())
+    }
+
+    fn take_at(&self, chat_id: ChatId, now: Instant) -> Option<Swipe> {
+        self.last
+            .lock()
+            .unwrap()
+            .remove(&chat_id)
+            .filter(|swipe| now.duration_since(swipe.at) <= UNDO_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_a_swipe_within_the_window() {
+        let journal = SwipeJournal::default();
+        let start = Instant::now();
+        let profile_id = Uuid::new_v4();
+        journal.record_at(ChatId(1), profile_id, ViewAction::Liked, start);
+        let swipe = journal.take_at(ChatId(1), start + UNDO_WINDOW).unwrap();
+        assert_eq!(swipe.profile_id, profile_id);
+        assert_eq!(swipe.action, ViewAction::Liked);
+    }
+
+    #[test]
+    fn forgets_a_swipe_past_the_window() {
+        let journal = SwipeJournal::default();
+        let start = Instant::now();
+        journal.record_at(ChatId(1), Uuid::new_v4(), ViewAction::Skipped, start);
+        let late = start + UNDO_WINDOW + Duration::from_millis(1);
+        assert!(journal.take_at(ChatId(1), late).is_none());
+    }
+
+    #[test]
+    fn undoes_only_the_last_swipe_once() {
+        let journal = SwipeJournal::default();
+        let start = Instant::now();
+        let last = Uuid::new_v4();
+        journal.record_at(ChatId(1), Uuid::new_v4(), ViewAction::Skipped, start);
+        journal.record_at(ChatId(1), last, ViewAction::Liked, start);
+        assert_eq!(journal.take_at(ChatId(1), start).unwrap().profile_id, last);
+        assert!(journal.take_at(ChatId(1), start).is_none());
+        assert!(journal.take_at(ChatId(2), start).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use axum::Router;
+use axum::routing::get;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use teloxide::prelude::*;
+use teloxide::update_listeners::UpdateListener;
+use teloxide::update_listeners::webhooks::{self, Options};
+use url::Url;
+
+pub const DEFAULT_ADDRESS: &str = "0.0.0.0:8443";
+
+/// Where Telegram delivers updates when the bot runs behind a webhook instead
+/// of long polling.
+///
+/// The listener speaks plain HTTP, TLS is expected to be terminated by a
+/// reverse proxy forwarding `url` to `address`. The public and the local path
+/// may differ, `path` is the one the proxy forwards to.
+///
+/// With `skip_setup` the webhook isn't registered with Telegram, which allows
+/// posting fake updates locally:
+///
+/// ```text
+/// curl -H 'X-Telegram-Bot-Api-Secret-Token: <secret>' \
+///      -H 'Content-Type: application/json' \
+///      -d @update.json http://localhost:8443/webhook
+/// ```
+#[derive(Clone)]
+pub struct Webhook {
+    url: Url,
+    address: SocketAddr,
+    path: String,
+    secret: String,
+    skip_setup: bool,
+}
+
+impl Webhook {
+    pub fn new(
+        url: Url,
+        address: SocketAddr,
+        path: String,
+        secret: String,
+        skip_setup: bool,
+    ) -> Self {
+        Self {
+            url,
+            address,
+            path,
+            secret,
+            skip_setup,
+        }
+    }
+
+    /// The update listener, the future resolving once it's stopped and the
+    /// routes to serve: the webhook itself and `/health`.
+    fn server(
+        &self,
+    ) -> (
+        impl UpdateListener<Err = Infallible> + use<>,
+        impl Future<Output = ()> + use<>,
+        Router,
+    ) {
+        let options = Options::new(self.address, self.url.clone())
+            .path(self.path.clone())
+            .secret_token(self.secret.clone());
+        let (listener, stop_flag, router) = webhooks::axum_no_setup(options);
+        let router = router.route("/health", get(|| async { "ok" }));
+        (listener, stop_flag, router)
+    }
+
+    /// Registers the webhook and starts the HTTP server next to a `/health`
+    /// endpoint. Once the returned listener is stopped, the server finishes the
+    /// requests in flight and the webhook is removed again.
+    pub async fn listen(self, bot: Bot) -> anyhow::Result<impl UpdateListener<Err = Infallible>> {
+        if !self.skip_setup {
+            bot.set_webhook(self.url.clone())
+                .secret_token(self.secret.clone())
+                .await?;
+        }
+        let (mut listener, stop_flag, router) = self.server();
+        let tcp_listener = tokio::net::TcpListener::bind(self.address).await?;
+        log::info!("Listening for webhook updates on {}", self.address);
+        let stop_token = listener.stop_token();
+        let skip_setup = self.skip_setup;
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(tcp_listener, router)
+                .with_graceful_shutdown(stop_flag)
+                .await
+            {
+                log::error!("Webhook server failed: {}", err);
+                stop_token.stop();
+            }
+            if !skip_setup && let Err(err) = bot.delete_webhook().await {
+                log::error!("Could not delete the webhook: {}", err);
+            }
+        });
+        Ok(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    const SECRET: &str = "s3cret";
+    const UPDATE: &str = r#"{
+        "update_id": 1,
+        "message": {
+            "message_id": 1,
+            "date": 0,
+            "chat": {"id": 42, "type": "private", "first_name": "Tom"},
+            "from": {"id": 42, "is_bot": false, "first_name": "Tom"},
+            "text": "/start"
+        }
+    }"#;
+
+    fn webhook() -> Webhook {
+        Webhook::new(
+            Url::parse("https://example.com/webhook").unwrap(),
+            DEFAULT_ADDRESS.parse().unwrap(),
+            "/webhook".to_string(),
+            SECRET.to_string(),
+            true,
+        )
+    }
+
+    fn update(secret: &str) -> Request<Body> {
+        Request::post("/webhook")
+            .header("X-Telegram-Bot-Api-Secret-Token", secret)
+            .header("Content-Type", "application/json")
+            .body(Body::from(UPDATE))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_updates_with_the_secret() {
+        let (_listener, _stop_flag, router) = webhook().server();
+        let response = router.oneshot(update(SECRET)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rejects_updates_with_a_wrong_secret() {
+        let (_listener, _stop_flag, router) = webhook().server();
+        let response = router.oneshot(update("guess")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn answers_health_checks() {
+        let (_listener, _stop_flag, router) = webhook().server();
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"ok");
+    }
+}